@@ -0,0 +1,483 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use bevy_asset::prelude::*;
+use bevy_image::Image;
+use bevy_log::warn;
+use serde::Deserialize;
+
+use crate::aseprite_data::FrameTag;
+use crate::SpriteSheet;
+
+/// A named range of frames in a sprite sheet, created from an aseprite animation tag or defined with
+/// [SpriteSheet::define_animation].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct AnimationRange {
+    /// The index of the first frame of the animation
+    pub from: usize,
+    /// The index of the last frame of the animation (inclusive)
+    pub to: usize,
+    /// The direction the frames are played in
+    pub direction: Direction,
+    /// How often the animation is played before it stops, None if it repeats forever
+    pub repeat: Option<u32>,
+    /// The indices of the frames, if they were defined explicitly instead of the range from..=to.
+    /// From and to are then the smallest and largest index.
+    pub frames: Option<Vec<usize>>,
+}
+
+impl AnimationRange {
+    pub fn new(from: usize, to: usize, direction: Direction) -> Self {
+        Self { from, to, direction, repeat: None, frames: None }
+    }
+
+    /// Create an animation of the given frame indices, which are played in the given order and direction.
+    pub fn from_frames(frames: Vec<usize>, direction: Direction) -> Self {
+        Self {
+            from: frames.iter().copied().min().unwrap_or_default(),
+            to: frames.iter().copied().max().unwrap_or_default(),
+            direction,
+            repeat: None,
+            frames: Some(frames),
+        }
+    }
+
+    /// Return a copy of this animation with all frame indices moved by the given offset.
+    pub(crate) fn offset(&self, offset: usize) -> Self {
+        Self {
+            from: self.from + offset,
+            to: self.to + offset,
+            frames: self.frames.as_ref().map(|frames| frames.iter().map(|i| i + offset).collect()),
+            ..self.clone()
+        }
+    }
+
+    /// Return the range from the first to the last frame of this animation, ignoring its direction.
+    pub fn as_range(&self) -> RangeInclusive<usize> {
+        self.from..=self.to
+    }
+
+    /// Return true if the frame with the given index is part of this animation.
+    pub fn contains(&self, index: usize) -> bool {
+        match &self.frames {
+            Some(frames) => frames.contains(&index),
+            None => self.as_range().contains(&index)
+        }
+    }
+
+    /// Play the animation the given number of times before it stops, instead of repeating it forever.
+    pub fn with_repeat(mut self, repeat: u32) -> Self {
+        self.repeat = Some(repeat);
+        self
+    }
+
+    /// Return the frame indices of one cycle of this animation, in the order they are played.
+    ///
+    /// A ping pong animation does not repeat its first and last frame, so "0..=2" results in 0, 1, 2, 1.
+    pub fn frame_sequence(&self) -> Vec<usize> {
+        let forward = self.frames.clone().unwrap_or_else(|| (self.from..=self.to).collect());
+        let backward = forward.iter().rev().copied().collect::<Vec<_>>();
+        let inner = |frames: &[usize]| frames.iter().skip(1).take(frames.len().saturating_sub(2)).copied().collect::<Vec<_>>();
+
+        match self.direction {
+            Direction::Forward => forward,
+            Direction::Reverse => backward,
+            Direction::PingPong => forward.iter().copied().chain(inner(&backward)).collect(),
+            Direction::PingPongReverse => backward.iter().copied().chain(inner(&forward)).collect(),
+        }
+    }
+}
+
+impl From<&FrameTag> for AnimationRange {
+    fn from(tag: &FrameTag) -> Self {
+        AnimationRange {
+            repeat: tag.repeat,
+            ..AnimationRange::new(tag.from, tag.to, tag.direction)
+        }
+    }
+}
+
+/// The direction the frames of an animation are played in, like defined by aseprite.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Deserialize)]
+pub enum Direction {
+    #[default]
+    #[serde(rename = "forward")]
+    Forward,
+    #[serde(rename = "reverse")]
+    Reverse,
+    #[serde(rename = "pingpong")]
+    PingPong,
+    #[serde(rename = "pingpong_reverse")]
+    PingPongReverse,
+}
+
+/// Create the animations of a sheet from its tags. If two tags have the same name, the first one is used.
+pub(crate) fn animations_from_tags(tags: &[FrameTag]) -> HashMap<String, AnimationRange> {
+    let mut animations = HashMap::new();
+
+    for tag in tags {
+        animations.entry(tag.name.clone()).or_insert_with(|| AnimationRange::from(tag));
+    }
+
+    animations
+}
+
+/// Return the distinct names of the given tags, in the order of the tags.
+pub(crate) fn animation_names_from_tags(tags: &[FrameTag]) -> Vec<String> {
+    let mut names = Vec::<String>::new();
+
+    for tag in tags {
+        if !names.contains(&tag.name) {
+            names.push(tag.name.clone())
+        }
+    }
+
+    names
+}
+
+impl SpriteSheet {
+    /// Return the animation with the given name.
+    pub fn animation(&self, name: &str) -> Option<&AnimationRange> {
+        self.animations.get(name)
+    }
+
+    /// Return the names of all animations, in the order they were defined.
+    pub fn animation_names(&self) -> impl Iterator<Item=&str> {
+        self.animation_names.iter().map(String::as_str)
+    }
+
+    /// Return the name of the first animation whose frame range contains the given frame index.
+    ///
+    /// If several animations overlap at the index, the one defined first wins (the first matching tag in the aseprite data).
+    pub fn animation_containing(&self, index: usize) -> Option<&str> {
+        self.animation_names().find(|name| self
+            .animation(name)
+            .is_some_and(|animation| animation.contains(index))
+        )
+    }
+
+    /// Return the indices of all frames which are not part of any animation, ordered by their index.
+    /// Useful to find frames which were not tagged by mistake.
+    pub fn untagged_frames(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&index| !self.animations
+                .values()
+                .any(|animation| animation.contains(index))
+            )
+            .collect()
+    }
+
+    /// Return the frame images of one cycle of the animation with the given name together with how long each is shown,
+    /// in the order they are played (see [AnimationRange::frame_sequence]). This is everything needed to play the
+    /// animation without the sheet.
+    ///
+    /// Returns None if there is no animation with this name or a frame has no image, like the frames of sheets created
+    /// with [crate::SpriteSheetPlugin::with_regions].
+    pub fn animation_timeline(&self, name: &str) -> Option<Vec<(Handle<Image>, Duration)>> {
+        self.animation(name)?
+            .frame_sequence()
+            .into_iter()
+            .map(|i| Some((self.textures.get(i)?.clone(), self.duration_at(i)?)))
+            .collect()
+    }
+
+    /// Return the index of the frame which is played after the given frame in the animation with the given name,
+    /// following the direction of the animation and starting again after the last frame of a cycle.
+    ///
+    /// In ping pong animations, the frames between the first and last frame are played in both directions, so the next
+    /// frame depends on where the animation comes from. The previously played frame resolves this: in a ping pong
+    /// animation of the frames 0 to 2, the frame after 1 is 2 when coming from 0 and 0 when coming from 2. Without a
+    /// previous frame (or if it does not precede the current frame), the animation is assumed to play its first half.
+    ///
+    /// Returns None if there is no animation with this name or the frame is not part of the animation.
+    pub fn next_frame_in(&self, animation: &str, current: usize, previous: Option<usize>) -> Option<usize> {
+        let sequence = self.animation(animation)?.frame_sequence();
+        let steps = sequence.len();
+        let mut positions = (0..steps).filter(|&step| sequence[step] == current);
+        let first = positions.next()?;
+
+        let step = positions
+            .chain([first])
+            .find(|&step| previous == Some(sequence[(step + steps - 1) % steps]))
+            .unwrap_or(first);
+
+        Some(sequence[(step + 1) % steps])
+    }
+
+    /// Return the index of the frame which is shown after the given time has elapsed since the animation with the
+    /// given name started, using the frame durations and the direction of the animation.
+    ///
+    /// If looping is true, the animation starts again after each cycle. Otherwise, its last played frame is returned
+    /// for any later time. A frame with a duration of zero holds the animation, so it is returned for any later time
+    /// and the animation does not loop (see [SpriteSheet::hold_frames]).
+    ///
+    /// Returns None if there is no animation with this name, it has no frames or a frame is out of bounds.
+    pub fn frame_at_time(&self, name: &str, elapsed: Duration, looping: bool) -> Option<usize> {
+        let frames = self.animation(name)?.frame_sequence();
+        let durations = frames
+            .iter()
+            .map(|&i| self.duration_at(i))
+            .collect::<Option<Vec<_>>>()?;
+        if durations.is_empty() {
+            return None
+        }
+
+        let holds = durations.iter().any(Duration::is_zero);
+
+        // there is at least one frame and without a hold frame, every frame has a duration, so the total duration is not zero
+        let mut time = match looping && !holds {
+            true => Duration::from_nanos((elapsed.as_nanos() % durations.iter().sum::<Duration>().as_nanos()) as u64),
+            false => elapsed
+        };
+
+        for (frame, duration) in frames.iter().zip(durations) {
+            if duration.is_zero() || time < duration {
+                return Some(*frame)
+            }
+            time -= duration;
+        }
+
+        frames.last().copied()
+    }
+
+    /// Return the indices of the frames of the animation with the given name which have a duration of zero, in the
+    /// order they are played. Aseprite sheets use such frames to hold the animation until it is continued by the game,
+    /// for example when waiting for a gameplay event.
+    ///
+    /// Returns None if there is no animation with this name.
+    pub fn hold_frames(&self, name: &str) -> Option<Vec<usize>> {
+        Some(self.animation(name)?
+            .frame_sequence()
+            .into_iter()
+            .filter(|&i| self.duration_at(i).is_some_and(|duration| duration.is_zero()))
+            .collect())
+    }
+
+    /// Return the average number of frames per second of the animation with the given name, which is the number of
+    /// played frames of one cycle divided by the summed duration of these frames.
+    ///
+    /// Returns None if there is no animation with this name, a frame is out of bounds or the animation has no duration.
+    pub fn animation_fps(&self, name: &str) -> Option<f32> {
+        let frames = self.animation(name)?.frame_sequence();
+        let total = frames
+            .iter()
+            .map(|&i| self.duration_at(i))
+            .sum::<Option<Duration>>()?;
+
+        match total.is_zero() {
+            true => None,
+            false => Some(frames.len() as f32 / total.as_secs_f32())
+        }
+    }
+
+    /// Return the image handles of the animation with the given name, in the order they are played.
+    ///
+    /// Returns None if there is no animation with this name or a frame has no image, like an out of bounds frame or
+    /// the frames of sheets created with [crate::SpriteSheetPlugin::with_regions].
+    pub fn animation_frames(&self, name: &str) -> Option<Vec<Handle<Image>>> {
+        self.all_images_at(self.animation(name)?.frame_sequence())
+    }
+
+    /// Return the image handles of all animations keyed by their name, in the order they are played.
+    /// Animations with a frame without an image are left out, see [SpriteSheet::animation_frames].
+    pub fn animations(&self) -> HashMap<String, Vec<Handle<Image>>> {
+        self.animations
+            .iter()
+            .filter_map(|(name, animation)| Some((name.clone(), self.all_images_at(animation.frame_sequence())?)))
+            .collect()
+    }
+
+    /// Return the image handle of the first played frame of every animation keyed by its name, for example to show
+    /// a thumbnail of each animation.
+    pub fn animation_preview_frames(&self) -> HashMap<String, Handle<Image>> {
+        self.animations
+            .iter()
+            .filter_map(|(name, animation)| Some((name.clone(), self.textures.get(*animation.frame_sequence().first()?)?.clone())))
+            .collect()
+    }
+
+    /// Return the aseprite frame names of the animation with the given name, in the order they are played.
+    pub fn animation_frame_names(&self, name: &str) -> Option<Vec<&str>> {
+        self.animation(name)?
+            .frame_sequence()
+            .into_iter()
+            .map(|i| self.frames.get(i).map(|frame| frame.name.as_str()))
+            .collect()
+    }
+
+    /// Define an animation of the given frame indices, which are played in the given order and direction. This allows
+    /// using the animation functions, like [SpriteSheet::animation_frames], for sheets without aseprite tags.
+    ///
+    /// An existing animation with the same name is replaced. Indices outside of the sheet are left out with a warning.
+    pub fn define_animation(&mut self, name: &str, frames: Vec<usize>, direction: Direction) {
+        let frame_count = self.len();
+        let (frames, invalid): (Vec<_>, Vec<_>) = frames.into_iter().partition(|&i| i < frame_count);
+        if !invalid.is_empty() {
+            warn!("The animation {name} has the frames {invalid:?}, but the sheet only has {frame_count} frames. These frames are left out.");
+        }
+
+        if !self.animation_names.iter().any(|existing| existing == name) {
+            self.animation_names.push(name.to_string());
+        }
+
+        self.animations.insert(name.to_string(), AnimationRange::from_frames(frames, direction));
+    }
+
+    /// Return a playback range which loops over all frames of the sheet at the given frames per second,
+    /// to quickly get any sheet animating.
+    pub fn loop_all(&self, fps: f32) -> PlaybackRange {
+        PlaybackRange::new(0..self.len(), fps, PlayMode::Loop)
+    }
+
+    /// Set the range of every animation to the first and last frame whose name contains "#<animation name>".
+    /// Animations without a matching frame name keep their range.
+    pub(crate) fn resolve_animations_by_frame_name(&mut self) {
+        for (name, animation) in self.animations.iter_mut() {
+            let marker = format!("#{name}");
+            let mut matching = self.frames
+                .iter()
+                .enumerate()
+                .filter(|(_, frame)| frame.name
+                    .split_whitespace()
+                    .any(|part| part == marker)
+                )
+                .map(|(i, _)| i);
+
+            if let Some(from) = matching.next() {
+                animation.from = from;
+                animation.to = matching.last().unwrap_or(from);
+            }
+        }
+    }
+}
+
+/// An animation defined by frame indices at runtime, independent of any aseprite tags.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaybackRange {
+    /// The indices of the frames in the order they are played
+    pub frames: Vec<usize>,
+    /// How many frames are played per second
+    pub fps: f32,
+    /// How the animation continues after the last frame
+    pub mode: PlayMode,
+}
+
+impl PlaybackRange {
+    pub fn new(frames: impl IntoIterator<Item=usize>, fps: f32, mode: PlayMode) -> Self {
+        Self {
+            frames: frames.into_iter().collect(),
+            fps,
+            mode,
+        }
+    }
+}
+
+/// Defines how an animation continues after its last frame.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum PlayMode {
+    /// Start again with the first frame
+    #[default]
+    Loop,
+    /// Stop at the last frame
+    Once,
+    /// Play the frames backwards until the first frame is reached, then forwards again
+    PingPong,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Direction;
+    use crate::test_utils::sheet_with_tags;
+
+    #[test]
+    fn next_frame_in_forward_wraps_at_the_end() {
+        let sheet = sheet_with_tags(8, r#"{"name": "walk", "from": 4, "to": 7, "direction": "forward"}"#);
+
+        assert_eq!(sheet.next_frame_in("walk", 4, None), Some(5));
+        assert_eq!(sheet.next_frame_in("walk", 5, None), Some(6));
+        assert_eq!(sheet.next_frame_in("walk", 7, None), Some(4));
+        assert_eq!(sheet.next_frame_in("walk", 3, None), None);
+        assert_eq!(sheet.next_frame_in("run", 4, None), None);
+    }
+
+    #[test]
+    fn next_frame_in_reverse_wraps_at_the_start() {
+        let sheet = sheet_with_tags(8, r#"{"name": "walk", "from": 4, "to": 7, "direction": "reverse"}"#);
+
+        assert_eq!(sheet.next_frame_in("walk", 7, None), Some(6));
+        assert_eq!(sheet.next_frame_in("walk", 5, None), Some(4));
+        assert_eq!(sheet.next_frame_in("walk", 4, None), Some(7));
+    }
+
+    #[test]
+    fn next_frame_in_pingpong_turns_at_both_ends() {
+        let sheet = sheet_with_tags(8, r#"{"name": "walk", "from": 4, "to": 6, "direction": "pingpong"}"#);
+
+        assert_eq!(sheet.next_frame_in("walk", 4, None), Some(5));
+        assert_eq!(sheet.next_frame_in("walk", 4, Some(5)), Some(5));
+        assert_eq!(sheet.next_frame_in("walk", 5, Some(4)), Some(6));
+        assert_eq!(sheet.next_frame_in("walk", 6, Some(5)), Some(5));
+        assert_eq!(sheet.next_frame_in("walk", 5, Some(6)), Some(4));
+        assert_eq!(sheet.next_frame_in("walk", 5, None), Some(6));
+    }
+
+    #[test]
+    fn next_frame_in_pingpong_reverse_turns_at_both_ends() {
+        let sheet = sheet_with_tags(8, r#"{"name": "walk", "from": 4, "to": 6, "direction": "pingpong_reverse"}"#);
+
+        assert_eq!(sheet.next_frame_in("walk", 6, None), Some(5));
+        assert_eq!(sheet.next_frame_in("walk", 5, Some(6)), Some(4));
+        assert_eq!(sheet.next_frame_in("walk", 4, Some(5)), Some(5));
+        assert_eq!(sheet.next_frame_in("walk", 5, Some(4)), Some(6));
+        assert_eq!(sheet.next_frame_in("walk", 5, None), Some(4));
+    }
+
+    #[test]
+    fn next_frame_in_pingpong_of_two_frames_alternates() {
+        let sheet = sheet_with_tags(2, r#"{"name": "blink", "from": 0, "to": 1, "direction": "pingpong"}"#);
+
+        assert_eq!(sheet.next_frame_in("blink", 0, Some(1)), Some(1));
+        assert_eq!(sheet.next_frame_in("blink", 1, Some(0)), Some(0));
+    }
+
+    #[test]
+    fn define_animation_leaves_out_frames_outside_of_the_sheet() {
+        let mut sheet = sheet_with_tags(4, "");
+        sheet.define_animation("jump", vec![3, 4, 1, 9], Direction::Forward);
+
+        assert_eq!(sheet.animation("jump").and_then(|animation| animation.frames.clone()), Some(vec![3, 1]));
+        assert_eq!(sheet.next_frame_in("jump", 1, None), Some(3));
+    }
+
+    #[test]
+    fn frame_at_time_of_an_empty_animation_is_none() {
+        let mut sheet = sheet_with_tags(4, "");
+        sheet.define_animation("empty", vec![], Direction::Forward);
+        sheet.define_animation("outside", vec![7, 9], Direction::Forward);
+
+        assert_eq!(sheet.frame_at_time("empty", Duration::from_millis(250), true), None);
+        assert_eq!(sheet.frame_at_time("outside", Duration::from_millis(250), true), None);
+        assert_eq!(sheet.frame_at_time("outside", Duration::from_millis(250), false), None);
+    }
+
+    #[test]
+    fn frames_without_images_do_not_panic() {
+        let sheet = sheet_with_tags(4, r#"{"name": "walk", "from": 0, "to": 3, "direction": "forward"}"#);
+
+        assert_eq!(sheet.len(), 4);
+        assert_eq!(sheet.animation_frames("walk"), None);
+        assert!(sheet.animations().is_empty());
+        assert!(sheet.images_at(0..4).is_empty());
+        assert!(sheet.handles_for(&sheet.loop_all(12.0)).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "the frame index 4 is out of bounds")]
+    fn out_of_bounds_frames_panic() {
+        let sheet = sheet_with_tags(4, "");
+        sheet.images_at([0, 4]);
+    }
+}
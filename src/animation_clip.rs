@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use bevy_animation::prelude::*;
+#[cfg(feature = "bevy_sprite")]
+use bevy_ecs::prelude::*;
+#[cfg(feature = "bevy_sprite")]
+use bevy_sprite::Sprite;
+
+use crate::SpriteSheet;
+
+/// Animation event which is triggered by the clips created with [SpriteSheet::to_animation_clips]
+/// whenever the shown frame changes.
+#[derive(AnimationEvent, Clone, Debug)]
+pub struct SpriteFrameEvent {
+    /// The index of the frame in its sprite sheet, which is also its texture atlas index
+    pub index: usize,
+}
+
+impl SpriteSheet {
+    /// Create an animation clip for every animation of this sheet. Each clip triggers a [SpriteFrameEvent]
+    /// whenever a frame starts, using the frame durations and the direction of the animation.
+    ///
+    /// The clips only trigger events and do not change the sprite themselves. To show the frames, render the sheet
+    /// with a texture atlas (see [SpriteSheetPlugin::with_atlases](crate::SpriteSheetPlugin::with_atlases)), put the
+    /// `Sprite` on the entity with the `AnimationPlayer` and add the [apply_sprite_frame_events] observer (with the
+    /// `bevy_sprite` feature), e.g. `app.add_observer(apply_sprite_frame_events)`.
+    pub fn to_animation_clips(&self) -> HashMap<String, AnimationClip> {
+        self.animations
+            .iter()
+            .map(|(name, animation)| {
+                let mut clip = AnimationClip::default();
+                let mut time = 0.0;
+
+                for index in animation.frame_sequence() {
+                    clip.add_event(time, SpriteFrameEvent { index });
+                    time += self.duration_at(index).unwrap_or_default().as_secs_f32();
+                }
+
+                clip.set_duration(time);
+                (name.clone(), clip)
+            })
+            .collect()
+    }
+}
+
+/// Observer which shows the frame of every [SpriteFrameEvent] by setting the texture atlas index of the `Sprite` on
+/// the entity of the animation player which triggered the event. Sprites without a texture atlas are not changed.
+///
+/// Add it with `app.add_observer(apply_sprite_frame_events)`, see [SpriteSheet::to_animation_clips].
+#[cfg(feature = "bevy_sprite")]
+pub fn apply_sprite_frame_events(event: On<SpriteFrameEvent>, mut sprites: Query<&mut Sprite>) {
+    let Ok(mut sprite) = sprites.get_mut(event.trigger().animation_player) else {
+        return
+    };
+
+    if let Some(atlas) = sprite.texture_atlas.as_mut() {
+        atlas.index = event.index;
+    }
+}
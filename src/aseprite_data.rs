@@ -1,51 +1,512 @@
 use std::cmp::Ordering::*;
 use std::collections::HashMap;
+use std::time::Duration;
 
 use bevy_asset::prelude::*;
+use bevy_color::{Color, Srgba};
+use bevy_image::ImageSampler;
+use bevy_log::warn;
 use bevy_reflect_derive::Reflect;
 use pad::p;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
+use crate::animation::Direction;
+use crate::error::SpriteSheetError;
+use crate::frame_info::FrameInfo;
 use crate::rect::Rect;
 
 /// Represents the json data for a sprite sheet that can be generated when exporting a sheet.
 /// Used to load sheets from images using the data from the json file.
+///
+/// Unknown fields are ignored and all fields which were not written by every aseprite version have defaults,
+/// so exports of older and newer aseprite versions can be parsed.
 #[derive(Asset, Reflect, Deserialize, Clone)]
 #[reflect(opaque)]
 pub struct AsepriteData {
-    frames: HashMap<String, FrameValue>,
+    /// All frames of the sheet, keyed by their frame name.
+    #[serde(default)]
+    pub frames: HashMap<String, FrameValue>,
+    /// Additional information about the sheet, like its animation tags.
+    #[serde(default)]
+    pub meta: Meta,
+    /// The pages of an atlas which is split into several images, stored in the "textures" array of the Phaser 3
+    /// multi atlas format (TexturePacker's "Phaser 3" export). Each page creates its own sheet,
+    /// see [SpriteSheetSource::pages](crate::SpriteSheetSource::pages).
+    #[serde(rename = "textures", default)]
+    pub pages: Vec<SheetPage>,
+}
+
+/// A page of a Phaser 3 multi atlas, whose frames are stored in one image. Like any other aseprite data, the json
+/// file of the atlas must end with ".aseprite.json" to be loaded.
+#[derive(Deserialize, Clone)]
+pub struct SheetPage {
+    /// The file name of the image of this page, relative to the json file.
+    pub image: String,
+    /// The pixel format of the image of this page, like "RGBA8888".
+    #[serde(default)]
+    pub format: Option<String>,
+    /// The size of the image of this page in pixels.
+    #[serde(default)]
+    pub size: Option<Size>,
+    /// All frames of this page, in the order they were packed.
+    #[serde(default)]
+    pub frames: Vec<PageFrame>,
+}
+
+/// A frame of a [SheetPage], which is named by its file name instead of being keyed by it.
+#[derive(Deserialize, Clone)]
+pub struct PageFrame {
+    /// The name of the frame, like "walk 0.aseprite".
+    pub filename: String,
+    #[serde(flatten)]
+    pub value: FrameValue,
 }
 
 impl AsepriteData {
+    /// Parse aseprite data from the given json bytes. The path is only used to describe the
+    /// offending file if the json is invalid.
+    pub fn from_json(path: &str, bytes: &[u8]) -> Result<Self, SpriteSheetError> {
+        serde_json::from_slice(bytes).map_err(|source| SpriteSheetError::InvalidJson {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    /// Return all tags whose frame range is not within the frames of the sheet, which would select missing frames.
+    pub fn invalid_tags(&self) -> Vec<&FrameTag> {
+        let frame_count = self.frames.len();
+
+        self.meta.frame_tags
+            .iter()
+            .filter(|tag| tag.from >= frame_count || tag.to >= frame_count)
+            .collect()
+    }
+
+    /// Return the version of aseprite which exported the sheet, like "1.3.7", if it is stored in the meta data.
+    pub fn aseprite_version(&self) -> Option<&str> {
+        self.meta.version.as_deref()
+    }
+
+    /// Return the pixel format of the sheet image declared in the meta data, like "RGBA8888" or "I8" for sheets
+    /// exported with indexed colors.
+    pub fn declared_format(&self) -> Option<&str> {
+        self.meta.format.as_deref()
+    }
+
+    /// Return the background color of the canvas, if it is stored in the meta data and is a valid hex color.
+    pub fn background_color(&self) -> Option<Color> {
+        let hex = self.meta.background_color.as_deref()?;
+        match Srgba::hex(hex) {
+            Ok(color) => Some(color.into()),
+            Err(_) => {
+                warn!("The background color {hex} is not a valid hex color");
+                None
+            }
+        }
+    }
+
     /// Returns an rectangle iterator for this aseprite data.
     /// All sub sprite names have a name like "<sheet name> <n>.aseprite", where
     /// <sheet name> is the name of the sprite sheet and <n> is the index.
     /// To return an ordered iterator, the entries must be sorted by key regarding length
     /// and name.
     pub fn rect_iter(&self) -> impl IntoIterator<Item=Rect> + '_ {
+        self.sorted_frames()
+            .into_iter()
+            .map(|(_, fv)| fv.frame)
+            .map(|f| Rect::new(p!(f.x, f.y), f.w, f.h))
+    }
+
+    /// Returns the information about all frames, in the same order as [AsepriteData::rect_iter].
+    pub fn frame_infos(&self) -> impl IntoIterator<Item=FrameInfo> + '_ {
+        self.sorted_frames()
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, fv))| FrameInfo {
+                name: name.clone(),
+                duration: Duration::from_millis(fv.duration),
+                trimmed: fv.trimmed,
+                sprite_source_size: fv.sprite_source_size.unwrap_or(Frame { x: 0, y: 0, w: fv.frame.w, h: fv.frame.h }),
+                source_size: fv.source_size.unwrap_or(Size { w: fv.frame.w, h: fv.frame.h }),
+                pivot: fv.pivot,
+                flip_x: fv.flip_x,
+                flip_y: fv.flip_y,
+                data: fv.data.clone().or_else(|| self.cel_data(index).map(str::to_string)),
+            })
+    }
+
+    /// Return the user data of the first cel at the given frame index which has any, starting at the bottom layer.
+    fn cel_data(&self, index: usize) -> Option<&str> {
+        self.meta.layers
+            .iter()
+            .flat_map(|layer| &layer.cels)
+            .find(|cel| cel.frame == index && cel.data.is_some())
+            .and_then(|cel| cel.data.as_deref())
+    }
+
+    /// Returns the frame entries sorted by their index, as described in [AsepriteData::rect_iter].
+    fn sorted_frames(&self) -> Vec<(&String, &FrameValue)> {
         let mut frames_vec = self.frames.iter().collect::<Vec<_>>();
         frames_vec.sort_by(|(ka, _), (kb, _)| match ka.len().cmp(&kb.len()) {
             Less => Less,
             Greater => Greater,
             Equal => ka.cmp(&kb)
         });
-
         frames_vec
-            .into_iter()
-            .map(|(_, fv)| fv.frame)
-            .map(|f| Rect::new(p!(f.x, f.y), f.w, f.h))
     }
 }
 
+/// A single entry in the frames map of the aseprite json data.
 #[derive(Deserialize, Clone)]
-struct FrameValue {
-    frame: Frame,
+pub struct FrameValue {
+    /// The position and size of the frame in the sheet image.
+    pub frame: Frame,
+    /// How long the frame is shown in milliseconds.
+    #[serde(default = "default_duration")]
+    pub duration: u64,
+    /// If the transparent border of the frame was removed when exporting.
+    #[serde(default)]
+    pub trimmed: bool,
+    /// The position and size of the trimmed frame inside the untrimmed frame.
+    #[serde(rename = "spriteSourceSize", default)]
+    pub sprite_source_size: Option<Frame>,
+    /// The size of the untrimmed frame.
+    #[serde(rename = "sourceSize", default)]
+    pub source_size: Option<Size>,
+    /// The pivot of this frame in pixels, relative to the top left corner of the untrimmed frame.
+    /// Not written by aseprite itself, but by custom exporters which store a pivot per frame.
+    #[serde(default)]
+    pub pivot: Option<Pivot>,
+    /// If the frame is stored horizontally mirrored in the sheet image, like by packers which deduplicate mirrored frames.
+    #[serde(rename = "flipX", default)]
+    pub flip_x: bool,
+    /// If the frame is stored vertically mirrored in the sheet image.
+    #[serde(rename = "flipY", default)]
+    pub flip_y: bool,
+    /// Custom user data of this frame. Not written by aseprite itself, see [Cel] for the user data of aseprite.
+    #[serde(default)]
+    pub data: Option<String>,
 }
 
-#[derive(Copy, Clone, Deserialize, Eq, PartialEq)]
-struct Frame {
-    x: usize,
-    y: usize,
-    w: usize,
-    h: usize,
-}
\ No newline at end of file
+/// Aseprite shows every frame for 100 milliseconds by default.
+pub(crate) fn default_duration() -> u64 {
+    100
+}
+
+/// The position and size of a frame in pixels.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Frame {
+    #[serde(deserialize_with = "deserialize_pixels")]
+    pub x: usize,
+    #[serde(deserialize_with = "deserialize_pixels")]
+    pub y: usize,
+    #[serde(deserialize_with = "deserialize_pixels")]
+    pub w: usize,
+    #[serde(deserialize_with = "deserialize_pixels")]
+    pub h: usize,
+}
+
+/// The size of a frame or image in pixels.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Size {
+    #[serde(deserialize_with = "deserialize_pixels")]
+    pub w: usize,
+    #[serde(deserialize_with = "deserialize_pixels")]
+    pub h: usize,
+}
+
+/// Some exporters write positions and sizes as floats, like 16.0. Floats are rounded to the nearest pixel,
+/// with a warning if they are not a whole number.
+fn deserialize_pixels<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let pixels = f64::deserialize(deserializer)?;
+
+    if !pixels.is_finite() || pixels < 0.0 {
+        return Err(serde::de::Error::custom(format!("{pixels} is not a valid number of pixels")))
+    }
+
+    if pixels.fract() != 0.0 {
+        warn!("The number of pixels {pixels} is not a whole number and is rounded to {}", pixels.round());
+    }
+
+    Ok(pixels.round() as usize)
+}
+
+/// The meta section of the aseprite json data.
+#[derive(Deserialize, Clone, Default)]
+pub struct Meta {
+    /// The application which exported the sheet, usually the aseprite website.
+    #[serde(default)]
+    pub app: Option<String>,
+    /// The version of the application which exported the sheet.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// The size of the sheet image in pixels.
+    #[serde(default)]
+    pub size: Option<Size>,
+    /// The pixel format of the sheet image, like "RGBA8888" or "I8" for indexed colors.
+    #[serde(default)]
+    pub format: Option<String>,
+    /// The background color of the canvas as a hex string like "#ff00ff" or "#ff00ffff", if it was exported.
+    #[serde(rename = "backgroundColor", default)]
+    pub background_color: Option<String>,
+    /// The sampler of the frames of this sheet, overriding the sampler set on the plugin.
+    /// Not written by aseprite itself, but can be added to the meta data by hand.
+    #[serde(default)]
+    pub sampler: Option<SheetSampler>,
+    /// The animation tags of the sheet.
+    #[serde(rename = "frameTags", default)]
+    pub frame_tags: Vec<FrameTag>,
+    /// The layers of the sprite, ordered from bottom to top.
+    #[serde(default)]
+    pub layers: Vec<LayerInfo>,
+    /// The slices of the sprite, which mark named regions like a pivot.
+    #[serde(default)]
+    pub slices: Vec<Slice>,
+}
+
+/// The filtering used when sampling the frames of a sheet, like `"sampler": "nearest"`.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq)]
+pub enum SheetSampler {
+    /// Nearest neighbor filtering, which keeps pixel art sharp.
+    #[serde(rename = "nearest")]
+    Nearest,
+    /// Linear filtering, which smooths the pixels.
+    #[serde(rename = "linear")]
+    Linear,
+}
+
+impl SheetSampler {
+    /// Return the image sampler with this filtering.
+    pub fn to_image_sampler(self) -> ImageSampler {
+        match self {
+            SheetSampler::Nearest => ImageSampler::nearest(),
+            SheetSampler::Linear => ImageSampler::linear(),
+        }
+    }
+}
+
+/// A named region of the sprite, which can change from frame to frame.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Slice {
+    pub name: String,
+    /// The keys of the slice, ordered by their frame. A key applies from its frame until the frame of the next key.
+    #[serde(default)]
+    pub keys: Vec<SliceKey>,
+}
+
+impl Slice {
+    /// Return the key which applies to the given frame, which is the key with the latest frame not after it.
+    /// Returns None if the first key starts after the frame.
+    pub fn key_at(&self, frame: usize) -> Option<&SliceKey> {
+        self.keys
+            .iter()
+            .filter(|key| key.frame <= frame)
+            .max_by_key(|key| key.frame)
+    }
+}
+
+/// The bounds and pivot of a slice, starting at a frame.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct SliceKey {
+    /// The index of the first frame this key applies to.
+    pub frame: usize,
+    /// The bounds of the slice in pixels, relative to the untrimmed frame.
+    pub bounds: Frame,
+    /// The pivot of the slice in pixels, relative to the top left corner of its bounds.
+    #[serde(default)]
+    pub pivot: Option<Pivot>,
+}
+
+/// A pivot point in pixels. It might lie outside of the frame.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Pivot {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A layer of the sprite the sheet was exported from.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct LayerInfo {
+    pub name: String,
+    /// The name of the group the layer is part of, if it is in a group.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// The opacity of the layer, from 0 (transparent) to 255 (opaque).
+    #[serde(default = "default_opacity")]
+    pub opacity: u8,
+    /// The aseprite name of the blend mode of the layer, like "normal" or "multiply".
+    #[serde(rename = "blendMode", default = "default_blend_mode")]
+    pub blend_mode: String,
+    /// The cels of the layer which have user data. Only exported by aseprite 1.3 and newer.
+    #[serde(default)]
+    pub cels: Vec<Cel>,
+}
+
+/// The content of a layer at a single frame, with the user data attached to it in aseprite.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Cel {
+    /// The index of the frame of the cel.
+    pub frame: usize,
+    /// The user data of the cel, like an event marker.
+    #[serde(default)]
+    pub data: Option<String>,
+}
+
+fn default_opacity() -> u8 {
+    255
+}
+
+fn default_blend_mode() -> String {
+    "normal".to_string()
+}
+
+/// An animation tag, which names a range of frames.
+#[derive(Deserialize, Clone)]
+pub struct FrameTag {
+    pub name: String,
+    /// The index of the first frame of the animation.
+    pub from: usize,
+    /// The index of the last frame of the animation (inclusive).
+    pub to: usize,
+    /// The direction the frames of the animation are played in.
+    #[serde(default)]
+    pub direction: Direction,
+    /// How often the animation is played before it stops. None if it repeats forever.
+    #[serde(default, deserialize_with = "deserialize_repeat")]
+    pub repeat: Option<u32>,
+    /// The user data of the tag. Only exported by aseprite 1.3 and newer.
+    #[serde(default)]
+    pub data: Option<String>,
+}
+
+/// Aseprite stores the repeat count of a tag as a string, like "3". Zero or a missing count means infinite repetition.
+fn deserialize_repeat<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repeat {
+        Number(u32),
+        Text(String),
+    }
+
+    let repeat = match Option::<Repeat>::deserialize(deserializer)? {
+        Some(Repeat::Number(count)) => count,
+        Some(Repeat::Text(text)) => text.trim().parse().map_err(serde::de::Error::custom)?,
+        None => 0
+    };
+
+    Ok(Some(repeat).filter(|&count| count > 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::SpriteSheetError;
+    use crate::test_utils::{parse, sheet_json, sheet_with_tags};
+    use crate::{AsepriteData, Frame};
+
+    #[test]
+    fn broken_json_is_an_invalid_json_error_with_its_path() {
+        let result = AsepriteData::from_json("animation/broken.aseprite.json", br#"{"frames": {"a": {"frame": "#);
+
+        match result {
+            Err(SpriteSheetError::InvalidJson { path, .. }) => assert_eq!(path, "animation/broken.aseprite.json"),
+            Ok(_) => panic!("broken json should not parse")
+        }
+    }
+
+    #[test]
+    fn repeat_is_read_from_strings_and_numbers() {
+        let tags = r#"{"name": "hit", "from": 0, "to": 1, "repeat": "3"},
+            {"name": "blink", "from": 0, "to": 1, "repeat": 2},
+            {"name": "idle", "from": 0, "to": 1, "repeat": "0"},
+            {"name": "walk", "from": 0, "to": 1}"#;
+        let data = parse(&sheet_json(2, tags));
+        let repeats = data.meta.frame_tags.iter().map(|tag| tag.repeat).collect::<Vec<_>>();
+
+        assert_eq!(repeats, [Some(3), Some(2), None, None]);
+        assert_eq!(sheet_with_tags(2, tags).animation("hit").and_then(|animation| animation.repeat), Some(3));
+    }
+
+    #[test]
+    fn invalid_repeat_is_an_error() {
+        let json = sheet_json(2, r#"{"name": "hit", "from": 0, "to": 1, "repeat": "often"}"#);
+
+        assert!(AsepriteData::from_json("test.aseprite.json", json.as_bytes()).is_err());
+    }
+
+    /// Exported by aseprite 1.2, before tags had user data or a repeat count.
+    const ASEPRITE_1_2: &str = r#"{
+        "frames": {
+            "hero 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 16, "h": 16}, "rotated": false, "trimmed": false, "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16}, "sourceSize": {"w": 16, "h": 16}, "duration": 100},
+            "hero 1.aseprite": {"frame": {"x": 16, "y": 0, "w": 16, "h": 16}, "rotated": false, "trimmed": false, "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16}, "sourceSize": {"w": 16, "h": 16}, "duration": 150}
+        },
+        "meta": {
+            "app": "http://www.aseprite.org/",
+            "version": "1.2.40-x64",
+            "image": "hero.png",
+            "format": "RGBA8888",
+            "size": {"w": 32, "h": 16},
+            "scale": "1",
+            "frameTags": [{"name": "walk", "from": 0, "to": 1, "direction": "forward"}],
+            "layers": [{"name": "Layer 1", "opacity": 255, "blendMode": "normal"}],
+            "slices": []
+        }
+    }"#;
+
+    /// Exported by aseprite 1.3, with tag and cel user data, a repeat count and fields unknown to this crate.
+    const ASEPRITE_1_3: &str = r#"{
+        "frames": {
+            "hero 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 16, "h": 16}, "rotated": false, "trimmed": false, "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16}, "sourceSize": {"w": 16, "h": 16}, "duration": 100},
+            "hero 1.aseprite": {"frame": {"x": 16, "y": 0, "w": 16, "h": 16}, "rotated": false, "trimmed": false, "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16}, "sourceSize": {"w": 16, "h": 16}, "duration": 150}
+        },
+        "meta": {
+            "app": "https://www.aseprite.org/",
+            "version": "1.3.7-x64",
+            "image": "hero.png",
+            "format": "RGBA8888",
+            "size": {"w": 32, "h": 16},
+            "scale": "1",
+            "frameTags": [{"name": "walk", "from": 0, "to": 1, "direction": "pingpong", "color": "#000000ff", "repeat": "2", "data": "loop"}],
+            "layers": [{"name": "Layer 1", "opacity": 255, "blendMode": "normal", "color": "#fe5b59ff", "cels": [{"frame": 1, "data": "hit", "color": "#000000ff"}]}],
+            "slices": []
+        }
+    }"#;
+
+    #[test]
+    fn exports_of_several_aseprite_versions_are_parsed() {
+        let old = parse(ASEPRITE_1_2);
+        let new = parse(ASEPRITE_1_3);
+
+        assert_eq!(old.aseprite_version(), Some("1.2.40-x64"));
+        assert_eq!(new.aseprite_version(), Some("1.3.7-x64"));
+
+        for data in [&old, &new] {
+            assert_eq!(data.frames.len(), 2);
+            assert_eq!(data.meta.frame_tags[0].name, "walk");
+            assert_eq!(data.meta.layers[0].name, "Layer 1");
+        }
+
+        assert_eq!(old.meta.frame_tags[0].repeat, None);
+        assert_eq!(old.meta.frame_tags[0].data, None);
+        assert_eq!(new.meta.frame_tags[0].repeat, Some(2));
+        assert_eq!(new.meta.frame_tags[0].data.as_deref(), Some("loop"));
+        assert_eq!(new.frame_infos().into_iter().nth(1).and_then(|frame| frame.data).as_deref(), Some("hit"));
+    }
+
+    #[test]
+    fn float_coordinates_are_rounded_to_pixels() {
+        let data = parse(r#"{"frames": {
+            "hero 0.aseprite": {"frame": {"x": 16.0, "y": 0.0, "w": 15.6, "h": 16}, "sourceSize": {"w": 16.0, "h": 16.4}}
+        }}"#);
+        let frame = &data.frames["hero 0.aseprite"];
+
+        assert_eq!(frame.frame, Frame { x: 16, y: 0, w: 16, h: 16 });
+        assert_eq!(frame.source_size.map(|size| (size.w, size.h)), Some((16, 16)));
+    }
+
+    #[test]
+    fn negative_coordinates_are_an_error() {
+        let json = r#"{"frames": {"hero 0.aseprite": {"frame": {"x": -1, "y": 0, "w": 16, "h": 16}}}}"#;
+
+        assert!(AsepriteData::from_json("test.aseprite.json", json.as_bytes()).is_err());
+    }
+}
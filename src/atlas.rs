@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_image::{Image, TextureAtlasLayout};
+use bevy_math::{URect, UVec2};
+
+use crate::{Rect, SpriteSheet, SpriteSheets};
+
+/// Texture atlas layouts for all sprite sheets, keyed by the same paths as [SpriteSheets].
+///
+/// Only created if enabled with [SpriteSheetPlugin::with_atlases](crate::SpriteSheetPlugin::with_atlases).
+#[derive(Resource)]
+pub struct SpriteSheetAtlases {
+    path_atlas_map: HashMap<String, SheetAtlas>,
+}
+
+struct SheetAtlas {
+    source: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+    frame_count: usize,
+}
+
+impl SpriteSheetAtlases {
+    /// Create a layout for every sheet which knows its sheet image.
+    pub(crate) fn new(sheets: &SpriteSheets, layouts: &mut Assets<TextureAtlasLayout>) -> Self {
+        SpriteSheetAtlases {
+            path_atlas_map: sheets
+                .path_sheet_map
+                .iter()
+                .filter_map(|(path, sheet)| Some((
+                    path.clone(),
+                    SheetAtlas {
+                        source: sheet.source()?,
+                        layout: layouts.add(sheet.atlas_layout()),
+                        frame_count: sheet.len(),
+                    }
+                )))
+                .collect()
+        }
+    }
+
+    /// Return everything required to spawn a sprite with a texture atlas for the sheet with the given path:
+    /// the sheet image, the atlas layout and the number of frames in the layout.
+    ///
+    /// The atlas index of a frame is the same as its index in the [SpriteSheet].
+    pub fn atlas(&self, path: &str) -> Option<(Handle<Image>, Handle<TextureAtlasLayout>, usize)> {
+        self.path_atlas_map
+            .get(path)
+            .map(|atlas| (atlas.source.clone(), atlas.layout.clone(), atlas.frame_count))
+    }
+}
+
+impl SpriteSheet {
+    /// Create a texture atlas layout for the sheet image, containing the rectangles of all frames.
+    pub fn atlas_layout(&self) -> TextureAtlasLayout {
+        self.layout_of(&self.rects)
+    }
+
+    /// Create a texture atlas layout for the sheet image, which only contains the frames of the animation with the
+    /// given name. This keeps the layout small if only one animation of a big sheet is used.
+    ///
+    /// Returns the layout and the atlas indices of the played frames, in the order they are played.
+    /// Every frame is only contained once, even if it is played several times.
+    pub fn atlas_layout_for_animation(&self, name: &str) -> Option<(TextureAtlasLayout, Vec<usize>)> {
+        let sequence = self.animation(name)?.frame_sequence();
+        let mut frames = sequence.clone();
+        frames.sort();
+        frames.dedup();
+
+        let rects = frames
+            .iter()
+            .map(|&i| self.rect_at(i))
+            .collect::<Option<Vec<_>>>()?;
+        let indices = sequence
+            .iter()
+            .map(|i| frames.binary_search(i).expect("every played frame should be in the layout"))
+            .collect();
+
+        Some((self.layout_of(&rects), indices))
+    }
+
+    /// Create a texture atlas layout for the sheet image containing the given rectangles.
+    fn layout_of(&self, rects: &[Rect]) -> TextureAtlasLayout {
+        let mut layout = TextureAtlasLayout::new_empty(self.source_size);
+
+        for rect in rects {
+            let min = UVec2::new(rect.position.x as u32, rect.position.y as u32);
+            layout.add_texture(URect::from_corners(min, min + UVec2::new(rect.width as u32, rect.height as u32)));
+        }
+
+        layout
+    }
+}
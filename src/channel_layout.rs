@@ -0,0 +1,52 @@
+use bevy_render::render_resource::TextureFormat;
+
+/// The order of the color channels inside a pixel with four 8 bit channels.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ChannelOrder {
+    Rgba,
+    Bgra,
+}
+
+/// Describes how the channels of a texture format are laid out inside a pixel.
+///
+/// This is the single source of truth for all pixel operations which need to know which byte is which channel,
+/// like comparing pixels against a color.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ChannelLayout {
+    /// The order of the channels
+    pub order: ChannelOrder,
+    /// The number of channels, which is also the number of bytes of a pixel
+    pub channels: usize,
+}
+
+impl ChannelLayout {
+    /// Return the channel layout of the given format, or None if it is not a supported format with four 8 bit channels.
+    pub fn of(format: TextureFormat) -> Option<Self> {
+        match format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => Some(ChannelLayout { order: ChannelOrder::Rgba, channels: 4 }),
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => Some(ChannelLayout { order: ChannelOrder::Bgra, channels: 4 }),
+            _ => None
+        }
+    }
+
+    /// Return the byte offset of the alpha channel inside a pixel.
+    pub fn alpha_offset(self) -> usize {
+        3
+    }
+
+    /// Read the given pixel as RGBA.
+    pub fn to_rgba(self, pixel: &[u8]) -> [u8; 4] {
+        match self.order {
+            ChannelOrder::Rgba => [pixel[0], pixel[1], pixel[2], pixel[3]],
+            ChannelOrder::Bgra => [pixel[2], pixel[1], pixel[0], pixel[3]],
+        }
+    }
+
+    /// Convert the given RGBA color to a pixel with this layout.
+    pub fn to_pixel(self, rgba: [u8; 4]) -> [u8; 4] {
+        match self.order {
+            ChannelOrder::Rgba => rgba,
+            ChannelOrder::Bgra => [rgba[2], rgba[1], rgba[0], rgba[3]],
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use bevy_asset::prelude::*;
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_log::warn;
+
+use crate::{ChannelLayout, LayerInfo, SpriteSheet};
+
+/// The blend modes of aseprite layers which can be composited.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    Difference,
+    Exclusion,
+    Addition,
+    Subtract,
+}
+
+impl BlendMode {
+    /// Return the blend mode with the given aseprite name, or None if it is not supported.
+    pub fn from_aseprite_name(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(BlendMode::Normal),
+            "multiply" => Some(BlendMode::Multiply),
+            "screen" => Some(BlendMode::Screen),
+            "overlay" => Some(BlendMode::Overlay),
+            "darken" => Some(BlendMode::Darken),
+            "lighten" => Some(BlendMode::Lighten),
+            "difference" => Some(BlendMode::Difference),
+            "exclusion" => Some(BlendMode::Exclusion),
+            "addition" => Some(BlendMode::Addition),
+            "subtract" => Some(BlendMode::Subtract),
+            _ => None
+        }
+    }
+
+    /// Blend a single color channel of the backdrop with the source, both between 0 and 1.
+    fn blend(&self, backdrop: f32, source: f32) -> f32 {
+        match self {
+            BlendMode::Normal => source,
+            BlendMode::Multiply => backdrop * source,
+            BlendMode::Screen => backdrop + source - backdrop * source,
+            BlendMode::Overlay => match backdrop <= 0.5 {
+                true => 2.0 * backdrop * source,
+                false => 1.0 - 2.0 * (1.0 - backdrop) * (1.0 - source)
+            },
+            BlendMode::Darken => backdrop.min(source),
+            BlendMode::Lighten => backdrop.max(source),
+            BlendMode::Difference => (backdrop - source).abs(),
+            BlendMode::Exclusion => backdrop + source - 2.0 * backdrop * source,
+            BlendMode::Addition => (backdrop + source).min(1.0),
+            BlendMode::Subtract => (backdrop - source).max(0.0),
+        }
+    }
+}
+
+/// Composite the given layer images into a single image, from the first (bottom) to the last (top) layer.
+/// Every layer is blended with its blend mode and opacity using the source over operator.
+///
+/// All images must have the same size and an 8 bit RGBA or BGRA format. The layers might have different channel orders,
+/// the result has the format of the first layer. If a layer has an unsupported blend mode,
+/// a warning is logged and a copy of the first layer is returned instead. Returns None if no layer is given.
+pub fn composite_layers(layers: &[(&Image, &LayerInfo)]) -> Option<Image> {
+    let (base, _) = layers.first()?;
+
+    let modes = layers
+        .iter()
+        .map(|(_, info)| BlendMode::from_aseprite_name(&info.blend_mode).ok_or(info))
+        .collect::<Result<Vec<_>, _>>();
+
+    let modes = match modes {
+        Ok(modes) => modes,
+        Err(info) => {
+            warn!("The blend mode {} of layer {} is not supported, only the first layer is used", info.blend_mode, info.name);
+            return Some((*base).clone())
+        }
+    };
+
+    let Some(layout) = ChannelLayout::of(base.texture_descriptor.format) else {
+        warn!("Layers with format {:?} can not be composited, only the first layer is used", base.texture_descriptor.format);
+        return Some((*base).clone())
+    };
+
+    let mut composited = vec![0; base.data.as_ref().map(Vec::len).unwrap_or_default()];
+
+    for ((image, info), mode) in layers.iter().zip(modes) {
+        let Some(data) = image.data.as_ref() else {
+            continue
+        };
+
+        let Some(layer_layout) = ChannelLayout::of(image.texture_descriptor.format) else {
+            warn!("The layer {} with format {:?} can not be composited and is skipped", info.name, image.texture_descriptor.format);
+            continue
+        };
+
+        composited
+            .chunks_exact_mut(layout.channels)
+            .zip(data.chunks_exact(layer_layout.channels))
+            // the source pixel is converted to the channel order of the result
+            .for_each(|(backdrop, source)| blend_pixel(backdrop, &layout.to_pixel(layer_layout.to_rgba(source)), info.opacity, mode));
+    }
+
+    Some(Image::new(
+        base.texture_descriptor.size,
+        base.texture_descriptor.dimension,
+        composited,
+        base.texture_descriptor.format,
+        RenderAssetUsages::default()
+    ))
+}
+
+/// Blend the source pixel with the given opacity over the backdrop pixel. Both pixels have their alpha as last channel.
+///
+/// The color channels are blended independently from each other, so their order does not matter.
+fn blend_pixel(backdrop: &mut [u8], source: &[u8], opacity: u8, mode: BlendMode) {
+    let to_unit = |value: u8| value as f32 / 255.0;
+    let alpha_backdrop = to_unit(backdrop[3]);
+    let alpha_source = to_unit(source[3]) * to_unit(opacity);
+    let alpha = alpha_source + alpha_backdrop * (1.0 - alpha_source);
+
+    if alpha <= 0.0 {
+        backdrop.fill(0);
+        return
+    }
+
+    for channel in 0..3 {
+        let color_backdrop = to_unit(backdrop[channel]);
+        let color_source = to_unit(source[channel]);
+        // the blend result only applies where the backdrop is opaque
+        let blended = (1.0 - alpha_backdrop) * color_source + alpha_backdrop * mode.blend(color_backdrop, color_source);
+        let color = (alpha_source * blended + alpha_backdrop * color_backdrop * (1.0 - alpha_source)) / alpha;
+        backdrop[channel] = (color * 255.0).round() as u8;
+    }
+
+    backdrop[3] = (alpha * 255.0).round() as u8;
+}
+
+/// Composite the frames of several sheets, one for each layer, into single images. The frame at index n of all
+/// layer sheets is composited into the frame at index n of the result, ordered from the bottom to the top layer.
+///
+/// This is meant for sheets exported with one sheet per layer, where the layer information comes from the
+/// `meta.layers` of the aseprite data. Frames which are not loaded in all layer sheets keep the frame of the first
+/// layer, so every frame stays at its index and the animations of the sheets still apply to the result.
+pub fn composite_layer_sheets(layer_sheets: &[(&SpriteSheet, &LayerInfo)], images: &mut Assets<Image>) -> Vec<Handle<Image>> {
+    let Some((base_sheet, _)) = layer_sheets.first() else {
+        return Vec::new()
+    };
+    let frame_count = layer_sheets.iter().map(|(sheet, _)| sheet.textures.len()).min().unwrap_or_default();
+
+    let composited = (0..frame_count)
+        .map(|index| {
+            let layers = layer_sheets
+                .iter()
+                .map(|(sheet, info)| Some((images.get(sheet.textures.get(index)?)?, *info)))
+                .collect::<Option<Vec<_>>>()?;
+            composite_layers(&layers)
+        })
+        .collect::<Vec<_>>();
+
+    composited
+        .into_iter()
+        .zip(&base_sheet.textures)
+        .map(|(image, base)| match image {
+            Some(image) => images.add(image),
+            None => base.clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::prelude::*;
+    use bevy_asset::RenderAssetUsages;
+    use bevy_image::Image;
+    use bevy_math::UVec2;
+    use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+    use super::composite_layer_sheets;
+    use crate::{AsepriteData, LayerInfo, SpriteSheet};
+
+    fn layer(name: &str) -> LayerInfo {
+        LayerInfo { name: name.to_string(), group: None, opacity: 255, blend_mode: "normal".to_string(), cels: Vec::new() }
+    }
+
+    fn pixel(images: &mut Assets<Image>, rgba: [u8; 4]) -> Handle<Image> {
+        images.add(Image::new(
+            Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            rgba.to_vec(),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default()
+        ))
+    }
+
+    fn layer_sheet(frames: Vec<Handle<Image>>) -> SpriteSheet {
+        let json = r#"{"frames": {
+            "layer 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 1, "h": 1}},
+            "layer 1.aseprite": {"frame": {"x": 1, "y": 0, "w": 1, "h": 1}}
+        }}"#;
+        let data = AsepriteData::from_json("layer.aseprite.json", json.as_bytes()).expect("the json should be valid");
+        SpriteSheet::new(frames, None, UVec2::new(2, 1), data)
+    }
+
+    #[test]
+    fn frames_missing_in_a_layer_keep_their_index() {
+        let mut images = Assets::<Image>::default();
+        let bottom = vec![pixel(&mut images, [255, 0, 0, 255]), pixel(&mut images, [0, 255, 0, 255])];
+        let unloaded = pixel(&mut images, [0, 0, 0, 0]);
+        images.remove(&unloaded);
+        let top = vec![unloaded, pixel(&mut images, [0, 0, 255, 255])];
+
+        let (bottom_sheet, top_sheet) = (layer_sheet(bottom.clone()), layer_sheet(top));
+        let (bottom_layer, top_layer) = (layer("bottom"), layer("top"));
+        let composited = composite_layer_sheets(&[(&bottom_sheet, &bottom_layer), (&top_sheet, &top_layer)], &mut images);
+
+        assert_eq!(composited.len(), 2);
+        assert_eq!(composited[0], bottom[0]);
+        assert_eq!(images.get(&composited[1]).and_then(|image| image.data.clone()), Some(vec![0, 0, 255, 255]));
+    }
+}
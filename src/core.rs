@@ -0,0 +1,327 @@
+//! The pure pixel logic used to create sprite sheets, without any dependency on bevy.
+//!
+//! All functions operate on raw, tightly packed pixel data (or data with a known row stride), so they
+//! can also be used by offline tools like atlas bakers or build scripts.
+
+use pad::p;
+
+pub use crate::error::RectError;
+pub use crate::rect::Rect;
+
+/// Options which change how the pixels of a rectangle are extracted from the sheet image.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ExtractOptions {
+    /// If true, the pixels of every row of the extracted frames are reversed
+    pub flip_x: bool,
+    /// If true, the rows of every extracted frame are reversed
+    pub flip_y: bool,
+}
+
+/// Check that all given rectangles have an area and lie within an image of the given size.
+/// Returns every offending rectangle.
+pub fn validate_rects(image_width: usize, image_height: usize, rects: &[Rect]) -> Result<(), Vec<RectError>> {
+    let errors = rects
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rect)| {
+            let rect = *rect;
+
+            if rect.width == 0 || rect.height == 0 {
+                return Some(RectError::ZeroArea { index, rect })
+            }
+
+            let out_of_bounds = rect.position.x < 0
+                || rect.position.y < 0
+                || rect.position.x as usize + rect.width > image_width
+                || rect.position.y as usize + rect.height > image_height;
+
+            match out_of_bounds {
+                true => Some(RectError::OutOfBounds { index, rect, image_width, image_height }),
+                false => None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors)
+    }
+}
+
+/// Copy the pixels inside the given rectangle out of the given image data.
+///
+/// The row stride is the number of bytes of one row in the data, the pixel width the number of bytes of one pixel.
+pub fn extract_rectangle(data: &[u8], rect: Rect, row_stride: usize, pixel_width: usize, options: ExtractOptions) -> Vec<u8> {
+    let mut extracted = Vec::with_capacity(rect.width * rect.height * pixel_width);
+    let start_index = row_stride * rect.position.y as usize;
+
+    for row in 0..rect.height {
+        // flipping is done per frame, so the rectangle still describes the same area of the sheet
+        let y = if options.flip_y { rect.height - 1 - row } else { row };
+        let start = start_index + y * row_stride + rect.position.x as usize * pixel_width;
+        let end = start + rect.width * pixel_width;
+
+        match options.flip_x {
+            true => data[start..end].chunks_exact(pixel_width).rev().for_each(|pixel| extracted.extend_from_slice(pixel)),
+            false => extracted.extend_from_slice(&data[start..end])
+        }
+    }
+
+    extracted
+}
+
+/// Rotate the given pixel data clockwise by the given number of quarter turns.
+/// Returns the rotated data with its width and height, which are swapped for an odd number of turns.
+pub fn rotate_90(data: &[u8], width: usize, height: usize, pixel_width: usize, quarter_turns: u8) -> (Vec<u8>, usize, usize) {
+    let mut rotated = (data.to_vec(), width, height);
+
+    for _ in 0..quarter_turns % 4 {
+        let (data, width, height) = rotated;
+        let mut turned = Vec::with_capacity(data.len());
+
+        // the rotated image has a width of the old height. Its rows are the old columns, read bottom to top
+        for x in 0..width {
+            for y in (0..height).rev() {
+                let start = (y * width + x) * pixel_width;
+                turned.extend_from_slice(&data[start..start + pixel_width]);
+            }
+        }
+
+        rotated = (turned, height, width);
+    }
+
+    rotated
+}
+
+/// Scale the given pixel data up by an integer factor, repeating every pixel factor x factor times (nearest neighbor).
+/// Returns the scaled data, which has a width and height of the given size multiplied by the factor.
+pub fn scale_nearest(data: &[u8], width: usize, height: usize, pixel_width: usize, factor: usize) -> Vec<u8> {
+    let mut scaled = Vec::with_capacity(data.len() * factor * factor);
+
+    for y in 0..height {
+        let row_start = scaled.len();
+
+        for x in 0..width {
+            let start = (y * width + x) * pixel_width;
+            for _ in 0..factor {
+                scaled.extend_from_slice(&data[start..start + pixel_width]);
+            }
+        }
+
+        // the scaled row is repeated for the remaining rows of the pixel
+        let row_end = scaled.len();
+        for _ in 1..factor {
+            scaled.extend_from_within(row_start..row_end);
+        }
+    }
+
+    scaled
+}
+
+/// Append a full mip chain to the given image data by repeatedly halving it with a box filter.
+/// Every channel must be 8 bit. Returns the data with all mip levels and the number of levels.
+pub fn mip_chain(mut data: Vec<u8>, mut width: usize, mut height: usize, pixel_width: usize) -> (Vec<u8>, u32) {
+    let mut level = data.clone();
+    let mut level_count = 1;
+
+    while width > 1 || height > 1 {
+        let next_width = (width / 2).max(1);
+        let next_height = (height / 2).max(1);
+        level = downsample(&level, width, height, next_width, next_height, pixel_width);
+        data.extend_from_slice(&level);
+
+        width = next_width;
+        height = next_height;
+        level_count += 1;
+    }
+
+    (data, level_count)
+}
+
+/// Downsample the given pixel data to the next size by averaging each 2x2 block of pixels.
+fn downsample(data: &[u8], width: usize, height: usize, next_width: usize, next_height: usize, pixel_width: usize) -> Vec<u8> {
+    let mut downsampled = Vec::with_capacity(next_width * next_height * pixel_width);
+
+    for y in 0..next_height {
+        for x in 0..next_width {
+            let xs = [x * 2, (x * 2 + 1).min(width - 1)];
+            let ys = [y * 2, (y * 2 + 1).min(height - 1)];
+
+            for channel in 0..pixel_width {
+                let sum = ys
+                    .iter()
+                    .flat_map(|sy| xs.iter().map(move |sx| data[(sy * width + sx) * pixel_width + channel] as u32))
+                    .sum::<u32>();
+                downsampled.push((sum / 4) as u8)
+            }
+        }
+    }
+
+    downsampled
+}
+
+/// Make every pixel of the given data fully transparent whose color channels are equal to the color channels of
+/// the key. The key is a pixel in the same channel order as the data and the alpha offset is the index of the
+/// 8 bit alpha channel inside a pixel, which is ignored when comparing.
+pub fn apply_color_key(data: &mut [u8], pixel_width: usize, key: &[u8], alpha_offset: usize) {
+    data
+        .chunks_exact_mut(pixel_width)
+        .filter(|pixel| (0..pixel_width).all(|channel| channel == alpha_offset || pixel[channel] == key[channel]))
+        .for_each(|pixel| pixel.fill(0))
+}
+
+/// Set every pixel on the outer edge of the given image data to the given pixel, which has the channel order of
+/// the data. The data holds layers of the given width and height, which all get a border.
+pub fn draw_border(data: &mut [u8], width: usize, height: usize, pixel_width: usize, pixel: &[u8]) {
+    if width == 0 || height == 0 {
+        return
+    }
+
+    for layer in data.chunks_exact_mut(width * height * pixel_width) {
+        for (y, row) in layer.chunks_exact_mut(width * pixel_width).enumerate() {
+            match y == 0 || y == height - 1 {
+                true => row.chunks_exact_mut(pixel_width).for_each(|target| target.copy_from_slice(pixel)),
+                false => {
+                    row[..pixel_width].copy_from_slice(pixel);
+                    row[(width - 1) * pixel_width..].copy_from_slice(pixel);
+                }
+            }
+        }
+    }
+}
+
+/// Return true if no pixel of the given data has an alpha value above zero.
+/// The alpha offset is the index of the 8 bit alpha channel inside a pixel.
+pub fn is_transparent(data: &[u8], pixel_width: usize, alpha_offset: usize) -> bool {
+    data
+        .chunks_exact(pixel_width)
+        .all(|pixel| pixel[alpha_offset] == 0)
+}
+
+/// Return the smallest rectangle inside the given rectangle which contains every pixel with an alpha value above zero,
+/// or None if all pixels of the rectangle are transparent. The returned rectangle is in the coordinates of the image data.
+///
+/// The row stride is the number of bytes of one row in the data, the pixel width the number of bytes of one pixel and
+/// the alpha offset the index of the 8 bit alpha channel inside a pixel.
+pub fn opaque_bounds(data: &[u8], rect: Rect, row_stride: usize, pixel_width: usize, alpha_offset: usize) -> Option<Rect> {
+    let x0 = rect.position.x as usize;
+    let y0 = rect.position.y as usize;
+    let is_opaque = |x: usize, y: usize| data[y * row_stride + x * pixel_width + alpha_offset] > 0;
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (usize::MAX, usize::MAX, 0, 0);
+
+    for y in y0..y0 + rect.height {
+        for x in x0..x0 + rect.width {
+            if is_opaque(x, y) {
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+    }
+
+    match min_x {
+        usize::MAX => None,
+        _ => Some(Rect::new(
+            p!(min_x, min_y),
+            max_x - min_x + 1,
+            max_y - min_y + 1,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pad::p;
+
+    use super::{extract_rectangle, mip_chain, opaque_bounds, rotate_90, scale_nearest, validate_rects, ExtractOptions, Rect, RectError};
+
+    /// A 3x2 image with one byte per pixel and a row stride of 4 bytes, whose padding bytes are 9.
+    const PADDED: [u8; 8] = [
+        0, 1, 2, 9,
+        4, 5, 6, 9,
+    ];
+
+    fn extract(flip_x: bool, flip_y: bool) -> Vec<u8> {
+        extract_rectangle(&PADDED, Rect::new(p!(1, 0), 2, 2), 4, 1, ExtractOptions { flip_x, flip_y })
+    }
+
+    #[test]
+    fn rects_inside_the_image_are_valid() {
+        let rects = [Rect::new(p!(0, 0), 2, 2), Rect::new(p!(2, 1), 2, 3)];
+
+        assert_eq!(validate_rects(4, 4, &rects), Ok(()));
+    }
+
+    #[test]
+    fn every_invalid_rect_is_returned() {
+        let rects = [
+            Rect::new(p!(0, 0), 2, 2),
+            Rect::new(p!(1, 1), 0, 2),
+            Rect::new(p!(3, 0), 2, 2),
+            Rect::new(p!(-1, 0), 1, 1),
+        ];
+
+        assert_eq!(validate_rects(4, 4, &rects), Err(vec![
+            RectError::ZeroArea { index: 1, rect: rects[1] },
+            RectError::OutOfBounds { index: 2, rect: rects[2], image_width: 4, image_height: 4 },
+            RectError::OutOfBounds { index: 3, rect: rects[3], image_width: 4, image_height: 4 },
+        ]));
+    }
+
+    #[test]
+    fn rectangles_are_extracted_without_the_row_padding() {
+        assert_eq!(extract(false, false), [1, 2, 5, 6]);
+    }
+
+    #[test]
+    fn extracted_rectangles_are_flipped() {
+        assert_eq!(extract(true, false), [2, 1, 6, 5]);
+        assert_eq!(extract(false, true), [5, 6, 1, 2]);
+        assert_eq!(extract(true, true), [6, 5, 2, 1]);
+    }
+
+    #[test]
+    fn images_are_rotated_clockwise() {
+        let data = [
+            1, 2, 3,
+            4, 5, 6,
+        ];
+
+        assert_eq!(rotate_90(&data, 3, 2, 1, 1), (vec![4, 1, 5, 2, 6, 3], 2, 3));
+        assert_eq!(rotate_90(&data, 3, 2, 1, 2), (vec![6, 5, 4, 3, 2, 1], 3, 2));
+        assert_eq!(rotate_90(&data, 3, 2, 1, 4), (data.to_vec(), 3, 2));
+    }
+
+    #[test]
+    fn pixels_are_repeated_when_scaling() {
+        let data = [1, 10, 2, 20];
+
+        assert_eq!(scale_nearest(&data, 2, 1, 2, 2), [
+            1, 10, 1, 10, 2, 20, 2, 20,
+            1, 10, 1, 10, 2, 20, 2, 20,
+        ]);
+        assert_eq!(scale_nearest(&data, 2, 1, 2, 1), data);
+    }
+
+    #[test]
+    fn mip_levels_are_appended_until_one_pixel() {
+        assert_eq!(mip_chain(vec![0, 4, 8, 12], 2, 2, 1), (vec![0, 4, 8, 12, 6], 2));
+        assert_eq!(mip_chain(vec![0, 4, 8, 12], 4, 1, 1), (vec![0, 4, 8, 12, 2, 10, 6], 3));
+        assert_eq!(mip_chain(vec![7], 1, 1, 1), (vec![7], 1));
+    }
+
+    #[test]
+    fn opaque_bounds_only_contain_pixels_with_alpha() {
+        // a 4x3 image with two bytes per pixel, where the second byte is the alpha channel
+        let mut data = vec![0; 24];
+        data[8 + 2 + 1] = 255;
+        data[16 + 4 + 1] = 1;
+        data[6] = 255;
+
+        assert_eq!(opaque_bounds(&data, Rect::new(p!(0, 0), 4, 3), 8, 2, 1), Some(Rect::new(p!(1, 1), 2, 2)));
+        assert_eq!(opaque_bounds(&data, Rect::new(p!(2, 0), 2, 3), 8, 2, 1), Some(Rect::new(p!(2, 2), 1, 1)));
+        assert_eq!(opaque_bounds(&data, Rect::new(p!(3, 0), 1, 3), 8, 2, 1), None);
+    }
+}
@@ -0,0 +1,55 @@
+use thiserror::Error;
+
+use crate::rect::Rect;
+
+/// Errors which can occur while loading or creating sprite sheets.
+///
+/// These are returned by the functions which parse aseprite data themselves, like [AsepriteData::from_json](crate::AsepriteData::from_json)
+/// and the loader of ".spritesheet" files. The json files loaded by the plugin are parsed by the asset loader of
+/// bevy_common_assets instead, whose errors are logged with the path of the file and do not create a sheet.
+#[derive(Debug, Error)]
+pub enum SpriteSheetError {
+    /// The aseprite json file at the given path could not be parsed.
+    #[error("the aseprite json file {path} is invalid: {source}")]
+    InvalidJson {
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+/// A frame rectangle which can not be extracted from its image.
+#[derive(Debug, Error, Copy, Clone, Eq, PartialEq)]
+pub enum RectError {
+    /// The rectangle has no width or height.
+    #[error("the rectangle {rect:?} at index {index} has no area")]
+    ZeroArea {
+        index: usize,
+        rect: Rect,
+    },
+    /// The rectangle exceeds the bounds of the image.
+    #[error("the rectangle {rect:?} at index {index} exceeds the image size of {image_width}x{image_height}")]
+    OutOfBounds {
+        index: usize,
+        rect: Rect,
+        image_width: usize,
+        image_height: usize,
+    },
+}
+
+/// A sprite sheet which does not have the expected number of frames, see [crate::SpriteSheets::expect_frame_count].
+#[derive(Debug, Error, Clone, Eq, PartialEq)]
+pub enum FrameCountMismatch {
+    /// No sheet with the given path was created.
+    #[error("the sprite sheet {path} was expected to have {expected} frames, but it was not created")]
+    Missing {
+        path: String,
+        expected: usize,
+    },
+    /// The sheet has a different number of frames.
+    #[error("the sprite sheet {path} was expected to have {expected} frames, but it has {actual}")]
+    Different {
+        path: String,
+        expected: usize,
+        actual: usize,
+    },
+}
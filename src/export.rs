@@ -0,0 +1,88 @@
+use std::io;
+use std::path::Path;
+
+use bevy_asset::prelude::*;
+use bevy_image::Image;
+use image::ColorType;
+
+use crate::{ChannelLayout, SpriteSheet};
+
+impl SpriteSheet {
+    /// Write every frame of this sheet as a png file to the given directory. The files are named by
+    /// the frame index, like "0.png", "1.png" and so on.
+    ///
+    /// Only frames with an 8 bit RGBA or BGRA format can be exported. Of frames with mip levels or several layers,
+    /// only the base level of the first layer is exported.
+    pub fn export_frames(&self, images: &Assets<Image>, dir: &Path) -> io::Result<()> {
+        for (index, handle) in self.textures.iter().enumerate() {
+            let image = images
+                .get(handle)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("frame {index} is not loaded")))?;
+            save_image(image, &dir.join(format!("{index}.png")))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn save_image(image: &Image, path: &Path) -> io::Result<()> {
+    let size = image.texture_descriptor.size;
+    let data = image
+        .data
+        .as_ref()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "the image has no data"))?;
+
+    let format = image.texture_descriptor.format;
+    let layout = ChannelLayout::of(format)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("frames with format {format:?} can not be exported")))?;
+
+    // the mip levels and further layers follow the base level of the first layer
+    let base_len = size.width as usize * size.height as usize * layout.channels;
+    let base = data
+        .get(..base_len)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "the image has less data than its size needs"))?;
+
+    let rgba = base
+        .chunks_exact(layout.channels)
+        .flat_map(|pixel| layout.to_rgba(pixel))
+        .collect::<Vec<_>>();
+
+    image::save_buffer(
+        path,
+        &rgba,
+        size.width,
+        size.height,
+        ColorType::Rgba8
+    ).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::RenderAssetUsages;
+    use bevy_image::Image;
+    use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+    use super::save_image;
+
+    #[test]
+    fn only_the_base_level_of_a_mipmapped_frame_is_exported() {
+        let mut frame = Image::new(
+            Extent3d { width: 2, height: 2, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            vec![10, 20, 30, 255, 40, 50, 60, 255, 70, 80, 90, 255, 100, 110, 120, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default()
+        );
+        // the 1x1 mip level
+        frame.data.as_mut().expect("the frame should have data").extend([55, 65, 75, 255]);
+        frame.texture_descriptor.mip_level_count = 2;
+
+        let path = std::env::temp_dir().join(format!("bevy_sprite_sheet_mipmapped_{}.png", std::process::id()));
+        save_image(&frame, &path).expect("the frame should be exported");
+        let exported = image::open(&path).expect("the exported frame should be a png").to_rgba8();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(exported.dimensions(), (2, 2));
+        assert_eq!(exported.into_raw(), &frame.data.as_ref().expect("the frame should have data")[..16]);
+    }
+}
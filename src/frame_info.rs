@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use bevy_math::UVec2;
+
+use crate::aseprite_data::{Frame, Pivot, Size};
+
+/// Information about a single frame of a sprite sheet, parsed from the aseprite data.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FrameInfo {
+    /// The name of the frame in the aseprite data
+    pub name: String,
+    /// How long the frame is shown
+    pub duration: Duration,
+    /// If the transparent border of the frame was removed when exporting
+    pub trimmed: bool,
+    /// The position and size of the trimmed frame inside the untrimmed frame.
+    /// Covers the whole untrimmed frame if the frame is not trimmed.
+    pub sprite_source_size: Frame,
+    /// The size of the untrimmed frame
+    pub source_size: Size,
+    /// The pivot of the frame in pixels relative to the untrimmed frame, if the frame defines one
+    pub pivot: Option<Pivot>,
+    /// If the frame is stored horizontally mirrored in the sheet image. The extracted frame is flipped back
+    pub flip_x: bool,
+    /// If the frame is stored vertically mirrored in the sheet image. The extracted frame is flipped back
+    pub flip_y: bool,
+    /// The user data of the frame, either of the frame itself or of the first of its cels with user data
+    pub data: Option<String>,
+}
+
+impl FrameInfo {
+    /// Return the position of the trimmed frame inside the untrimmed frame, in pixels.
+    /// Untrimmed frames have no offset, so this is always zero for them.
+    pub fn trim_offset(&self) -> UVec2 {
+        match self.trimmed {
+            true => UVec2::new(self.sprite_source_size.x as u32, self.sprite_source_size.y as u32),
+            false => UVec2::ZERO
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_math::UVec2;
+
+    use crate::test_utils::{parse, sheet_from_json};
+
+    const MIXED_TRIM: &str = r#"{
+        "frames": {
+            "hero 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 12, "h": 10}, "trimmed": true, "spriteSourceSize": {"x": 2, "y": 3, "w": 12, "h": 10}, "sourceSize": {"w": 16, "h": 16}},
+            "hero 1.aseprite": {"frame": {"x": 12, "y": 0, "w": 16, "h": 16}, "trimmed": false, "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16}, "sourceSize": {"w": 16, "h": 16}}
+        }
+    }"#;
+
+    #[test]
+    fn only_trimmed_frames_are_offset() {
+        let frames = parse(MIXED_TRIM).frame_infos().into_iter().collect::<Vec<_>>();
+
+        assert!(frames[0].trimmed);
+        assert_eq!(frames[0].trim_offset(), UVec2::new(2, 3));
+        assert!(!frames[1].trimmed);
+        assert_eq!(frames[1].trim_offset(), UVec2::ZERO);
+        assert!(sheet_from_json(MIXED_TRIM).has_trimmed_frames());
+    }
+}
@@ -0,0 +1,121 @@
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_image::{Image, TextureAtlasLayout};
+use bevy_platform::time::Instant;
+use bevy_state::prelude::*;
+use bevy_state::state::FreelyMutableState;
+
+use crate::core::ExtractOptions;
+use crate::{add_frame, advance_state, finish_sprite_sheet, frame_rects, insert_sprite_sheets, process_frame, split_image, MatchedSheet, Rect, SheetSettings, SpriteSheet, SpriteSheets};
+
+/// The progress of creating the sprite sheets incrementally, enabled with
+/// [SpriteSheetPlugin::with_frames_per_tick](crate::SpriteSheetPlugin::with_frames_per_tick).
+#[derive(Resource, Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SpriteSheetProgress {
+    /// The number of frames which were already created
+    pub created_frames: usize,
+    /// The number of frames of all sheets
+    pub total_frames: usize,
+}
+
+impl SpriteSheetProgress {
+    /// Return the progress as a value between 0 and 1.
+    pub fn fraction(&self) -> f32 {
+        match self.total_frames {
+            0 => 1.0,
+            total => self.created_frames as f32 / total as f32
+        }
+    }
+
+    /// Return true if all frames were created.
+    pub fn is_done(&self) -> bool {
+        self.created_frames >= self.total_frames
+    }
+}
+
+/// The sheets which are currently created incrementally.
+#[derive(Resource)]
+pub(crate) struct PendingSpriteSheets {
+    /// The sheets which still have frames to create
+    pending: Vec<PendingSheet>,
+    /// The sheets which are already done
+    created: Vec<(String, SpriteSheet)>,
+    /// When the creation of the sheets started
+    started: Instant,
+}
+
+struct PendingSheet {
+    matched: MatchedSheet,
+    rects: Vec<(Rect, ExtractOptions)>,
+    handles: Vec<Handle<Image>>,
+}
+
+/// Start creating the given sheets incrementally.
+pub(crate) fn start_incremental_creation(commands: &mut Commands, matched_sheets: Vec<MatchedSheet>, settings: &SheetSettings, started: Instant) {
+    let pending = matched_sheets
+        .into_iter()
+        .map(|matched| PendingSheet {
+            rects: frame_rects(&matched.data, settings.extract),
+            handles: Vec::new(),
+            matched,
+        })
+        .collect::<Vec<_>>();
+
+    commands.insert_resource(SpriteSheetProgress {
+        created_frames: 0,
+        total_frames: pending.iter().map(|sheet| sheet.rects.len()).sum(),
+    });
+    commands.insert_resource(PendingSpriteSheets {
+        pending,
+        created: Vec::new(),
+        started,
+    });
+}
+
+/// Create up to the given number of frames each time the system runs. When all frames are created, the sprite
+/// sheets are inserted and the state is switched.
+pub(crate) fn create_sprite_sheets_incrementally<S: States + FreelyMutableState>(
+    followup_state: Option<S>,
+    settings: SheetSettings,
+    frames_per_tick: usize,
+) -> impl Fn(Commands, ResMut<Assets<Image>>, ResMut<PendingSpriteSheets>, ResMut<SpriteSheetProgress>, ResMut<NextState<S>>, Option<ResMut<Assets<TextureAtlasLayout>>>) {
+    move |mut commands, mut images, mut pending_sheets, mut progress, mut next_state, layouts| {
+        let mut budget = frames_per_tick;
+
+        while let Some(sheet) = pending_sheets.pending.last_mut() {
+            if sheet.handles.len() == sheet.rects.len() {
+                let sheet = pending_sheets.pending.pop().expect("the pending sheet should exist");
+                let image_size = sheet.matched.image.size();
+                let mut created = finish_sprite_sheet(sheet.handles, sheet.matched.source, image_size, sheet.matched.data, &settings);
+                created.source_id = Some(sheet.matched.source_id);
+                created.source_path = sheet.matched.source_path;
+                created.user_meta = sheet.matched.user_meta;
+                pending_sheets.created.push((sheet.matched.key, created));
+                continue
+            }
+
+            if budget == 0 {
+                break
+            }
+
+            let (rect, options) = sheet.rects[sheet.handles.len()];
+            let frame = split_image(&sheet.matched.image, [rect], options)
+                .into_iter()
+                .next()
+                .expect("one frame should be created");
+            let previous = sheet.matched.previous.get(sheet.handles.len());
+            let frame = process_frame(frame, &settings.for_sheet(&sheet.matched.data));
+            sheet.handles.push(add_frame(&mut images, frame, previous));
+
+            progress.created_frames += 1;
+            budget -= 1;
+        }
+
+        if pending_sheets.pending.is_empty() {
+            let sprite_sheets = SpriteSheets::new(std::mem::take(&mut pending_sheets.created));
+            insert_sprite_sheets(&mut commands, sprite_sheets, &images, &settings, layouts, pending_sheets.started);
+            commands.remove_resource::<PendingSpriteSheets>();
+            advance_state(&mut next_state, &followup_state)
+        }
+    }
+}
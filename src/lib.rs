@@ -1,194 +1,2409 @@
+mod animation;
+#[cfg(feature = "bevy_animation")]
+mod animation_clip;
 mod aseprite_data;
+mod atlas;
+mod channel_layout;
+#[cfg(feature = "compositing")]
+mod compositing;
+pub mod core;
+mod error;
+#[cfg(feature = "export")]
+mod export;
+mod frame_info;
+mod incremental;
+mod pivot;
 mod rect;
+#[cfg(feature = "spritesheet_file")]
+mod sheet_file;
+mod sidecar;
+mod slice;
+mod source;
+#[cfg(feature = "bevy_sprite")]
+mod sprite;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod user_meta;
+#[cfg(feature = "offline")]
+mod validate;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use bevy_app::prelude::*;
 use bevy_asset::prelude::*;
-use bevy_asset::RenderAssetUsages;
+use bevy_asset::{AssetLoadFailedEvent, AssetPath, LoadState, RenderAssetUsages};
+use bevy_color::Color;
 use bevy_common_assets::json::JsonAssetPlugin;
+#[cfg(feature = "ron")]
+use bevy_common_assets::ron::RonAssetPlugin;
 use bevy_ecs::prelude::*;
+use bevy_log::{debug_span, error, info, info_span, warn};
+use bevy_math::{UVec2, Vec2};
+use bevy_platform::time::Instant;
 use bevy_state::prelude::*;
-use bevy_render::render_resource::Extent3d;
-use bevy_image::{Image, TextureFormatPixelInfo};
+use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_image::{Image, ImageSampler, TextureAtlasLayout, TextureFormatPixelInfo};
 use bevy_state::state::FreelyMutableState;
-use crate::aseprite_data::AsepriteData;
-use crate::rect::Rect;
+use bevy_tasks::ComputeTaskPool;
+use pad::p;
+use crate::animation::{animation_names_from_tags, animations_from_tags};
+use crate::core::ExtractOptions;
+use crate::user_meta::attach_user_meta;
+use crate::incremental::{create_sprite_sheets_incrementally, start_incremental_creation, PendingSpriteSheets};
+
+pub use crate::animation::{AnimationRange, Direction, PlaybackRange, PlayMode};
+#[cfg(feature = "bevy_animation")]
+pub use crate::animation_clip::SpriteFrameEvent;
+#[cfg(all(feature = "bevy_animation", feature = "bevy_sprite"))]
+pub use crate::animation_clip::apply_sprite_frame_events;
+pub use crate::aseprite_data::{AsepriteData, Cel, Frame, FrameTag, FrameValue, LayerInfo, Meta, PageFrame, Pivot, SheetPage, SheetSampler, Size, Slice, SliceKey};
+#[cfg(feature = "compositing")]
+pub use crate::compositing::{composite_layer_sheets, composite_layers, BlendMode};
+pub use crate::atlas::SpriteSheetAtlases;
+pub use crate::channel_layout::{ChannelLayout, ChannelOrder};
+pub use crate::error::{FrameCountMismatch, RectError, SpriteSheetError};
+pub use crate::frame_info::FrameInfo;
+pub use crate::incremental::SpriteSheetProgress;
+pub use crate::rect::Rect;
+#[cfg(feature = "spritesheet_file")]
+pub use crate::sheet_file::{write_sprite_sheet_file, SpriteSheetFile, SpriteSheetFileError, SpriteSheetFileLoader};
+pub use crate::sidecar::{FrameDefinition, SheetDefinition};
+pub use crate::slice::SliceInfo;
+pub use crate::source::{SourcePage, SpriteSheetSource};
+pub use crate::user_meta::SheetUserMeta;
+#[cfg(feature = "offline")]
+pub use crate::validate::{validate_folder, ValidationIssue, ValidationReport};
 
 /// Plugin which will create sprite sheets from loaded aseprite json assets with their matching image
-/// assets. The sheets will be loaded when entering CreateState and afterwards, the plugin will switch to NextState.
+/// assets. The sheets will be loaded when entering CreateState and afterwards, the plugin will switch to NextState,
+/// unless it was created with [SpriteSheetPlugin::new_without_transition].
 ///
 /// Important: The aseprite json assets and associated image assets must be loaded in before.
 pub struct SpriteSheetPlugin<CreateState: States + FreelyMutableState, NextState: States + FreelyMutableState> {
     /// The state the plugin will start creating all sprite sheets.
     loading_state: CreateState,
-    /// The state the plugin will switch to when all sprite sheets were created
-    next_state: NextState
+    /// The state the plugin will switch to when all sprite sheets were created, None to stay in the loading state
+    next_state: Option<NextState>,
+    /// Further pairs of loading and next states, for staged loading
+    additional_stages: Vec<(CreateState, NextState)>,
+    /// Settings which control how the sprite sheets are created
+    settings: SheetSettings,
+}
+
+impl <CreateState: States + FreelyMutableState> SpriteSheetPlugin<CreateState, CreateState> {
+    /// Create the sprite sheets when entering the given loading state, but stay in this state afterwards. This is for
+    /// apps which control the flow of their states themselves. The [SpriteSheets] resource (or
+    /// [SpriteSheetProgress] in incremental mode) tells when the sheets were created.
+    pub fn new_without_transition(loading_state: CreateState) -> Self {
+        Self { loading_state, next_state: None, additional_stages: Vec::new(), settings: SheetSettings::default() }
+    }
 }
 
 impl <CreateState: States + FreelyMutableState, NextState: States + FreelyMutableState> SpriteSheetPlugin<CreateState, NextState> {
     pub fn new(loading_state: CreateState, next_state: NextState) -> Self {
-        Self { loading_state, next_state }
+        Self { loading_state, next_state: Some(next_state), additional_stages: Vec::new(), settings: SheetSettings::default() }
+    }
+
+    /// Also create the sprite sheets when entering the given loading state and switch to the given next state afterwards.
+    ///
+    /// This allows loading assets in several stages. Every stage recreates the [SpriteSheets] resource from all
+    /// aseprite json and image assets which are loaded at that point, so sheets of earlier stages are kept
+    /// as long as their assets are still loaded.
+    pub fn with_additional_stage(mut self, loading_state: CreateState, next_state: NextState) -> Self {
+        self.additional_stages.push((loading_state, next_state));
+        self
+    }
+
+    /// Generate a full mip chain for every created frame image, using a box filter.
+    /// Disabled by default, as pixel art usually should not be mipmapped.
+    pub fn with_mipmaps(mut self, mipmaps: bool) -> Self {
+        self.settings.mipmaps = mipmaps;
+        self
+    }
+
+    /// Vertically flip every extracted frame. This is required for sheet images which store their rows
+    /// bottom to top. The frames are flipped individually, so the frame rectangles stay correct.
+    ///
+    /// Frames which are marked as mirrored with "flipX" or "flipY" in the json data are always flipped back, so
+    /// combined with this option, a frame marked with "flipY" is not flipped.
+    pub fn with_flip_y(mut self, flip_y: bool) -> Self {
+        self.settings.extract.flip_y = flip_y;
+        self
+    }
+
+    /// Resolve the frames of an animation tag by the frame names instead of the tag indices. The first and last
+    /// frame whose name contains "#<tag name>" define the range of the tag, so it stays correct when frames are reordered.
+    /// This requires exporting the sheet with a file name format containing the tag, like "{title} #{tag} {frame}".
+    ///
+    /// Tags without any matching frame name keep their index range.
+    pub fn with_tags_by_frame_name(mut self, tags_by_frame_name: bool) -> Self {
+        self.settings.tags_by_frame_name = tags_by_frame_name;
+        self
+    }
+
+    /// Also create the [SpriteSheetAtlases] resource, which holds a texture atlas layout for every sheet.
+    /// This allows rendering the sheets with a single texture and a [TextureAtlas](bevy_image::TextureAtlas) instead
+    /// of a separate image per frame.
+    ///
+    /// Requires the `Assets<TextureAtlasLayout>` resource, which is added by bevy's sprite plugin.
+    pub fn with_atlases(mut self, atlases: bool) -> Self {
+        self.settings.atlases = atlases;
+        self
+    }
+
+    /// Set the maximum number of pixels (width * height) a single frame can have. Sheets with a larger frame are
+    /// not created and an error is logged. This guards against untrusted json files which declare huge frames.
+    ///
+    /// Defaults to the area of a 8192x8192 image.
+    pub fn with_max_frame_pixels(mut self, max_frame_pixels: usize) -> Self {
+        self.settings.max_frame_pixels = Some(max_frame_pixels);
+        self
+    }
+
+    /// Set the maximum width and height of a sheet image, as declared by the size in the meta data of its json file.
+    /// Sheets which declare a larger or an empty image are not created and an error is logged. As the declared size is
+    /// checked before any image is matched, corrupt json files are rejected early. Sheets without a declared size are
+    /// not checked.
+    ///
+    /// Defaults to 16384, the largest texture size most desktop GPUs support.
+    pub fn with_max_sheet_size(mut self, max_sheet_size: usize) -> Self {
+        self.settings.max_sheet_size = Some(max_sheet_size);
+        self
+    }
+
+    /// Create the sprite sheets incrementally, only splitting the given number of frames per update. This spreads
+    /// the work and allocations of large sheets over several updates. The [SpriteSheetProgress] resource tracks the
+    /// progress, which can be used to show a loading bar. The plugin only switches to the next state and inserts
+    /// [SpriteSheets] when all frames were created.
+    ///
+    /// As the creation takes several updates, [SpriteSheets] does not exist yet in the OnEnter schedule of the loading
+    /// state, even for systems ordered `.after(SpriteSheetSet)`. Use the OnEnter schedule of the next state to access it.
+    pub fn with_frames_per_tick(mut self, frames_per_tick: usize) -> Self {
+        self.settings.frames_per_tick = Some(frames_per_tick.max(1));
+        self
+    }
+
+    /// Log every loaded image which has no matching aseprite json file when creating the sheets.
+    ///
+    /// Disabled by default, as an asset folder usually contains images which are not sprite sheets.
+    pub fn with_report_unmatched_images(mut self, report_unmatched_images: bool) -> Self {
+        self.settings.report_unmatched_images = report_unmatched_images;
+        self
+    }
+
+    /// Only split a sheet into its frames when it is requested for the first time with [SpriteSheets::get_sheet_or_build],
+    /// instead of splitting all sheets when entering the loading state. This makes the initial loading much faster
+    /// if only a few of many sheets are used, but spreads the cost of creating each sheet over the gameplay.
+    ///
+    /// Lazy mode takes precedence over [SpriteSheetPlugin::with_frames_per_tick]. Atlases are not created for lazy sheets.
+    pub fn with_lazy(mut self, lazy: bool) -> Self {
+        self.settings.lazy = lazy;
+        self
+    }
+
+    /// Remove the transparent border of every frame which was not trimmed when exporting, like aseprite's trim option
+    /// but when creating the sheets. The offset of the cropped frame is stored as trim information, see [FrameInfo].
+    ///
+    /// Fully transparent frames are cropped to a single transparent pixel.
+    pub fn with_auto_trim(mut self, auto_trim: bool) -> Self {
+        self.settings.auto_trim = auto_trim;
+        self
+    }
+
+    /// Keep the frame handles of a sheet stable when the sheets are created again, for example when entering the
+    /// loading state again after the assets were hot reloaded. Instead of adding new images, the images of the
+    /// existing handles are replaced in place, so handles cached by other systems show the reloaded frames.
+    ///
+    /// The handles are only reused if the sheet still has the same number of frames, otherwise new handles are created
+    /// and a warning is logged. Lazy sheets always get new handles.
+    pub fn with_stable_handles(mut self, stable_handles: bool) -> Self {
+        self.settings.stable_handles = stable_handles;
+        self
+    }
+
+    /// Call the given function on every extracted frame before it is added to the image assets, for example to add
+    /// an outline or recolor the frame. Runs before the mip chain is generated.
+    ///
+    /// The function runs while the sheets are created, so expensive functions delay the loading. It might run on
+    /// several threads of the compute task pool at once, see [SpriteSheetPlugin::with_max_parallelism].
+    pub fn with_frame_postprocess(mut self, postprocess: FramePostprocess) -> Self {
+        self.settings.frame_postprocess = Some(postprocess);
+        self
+    }
+
+    /// Do not split the sheets into frame images at all. The sheets only keep the handle of their sheet image and the
+    /// rectangles of the frames, which can be retrieved with [SpriteSheet::frame_region]. This avoids copying any
+    /// pixels and pairs well with [SpriteSheetPlugin::with_atlases] to render the frames from the sheet image.
+    ///
+    /// In region mode, the sheets have no frame images, so [SpriteSheet::textures] is empty and functions which return
+    /// frame images, like [SpriteSheet::image_at], can not be used. This takes precedence over
+    /// [SpriteSheetPlugin::with_frames_per_tick], as there is nothing to spread over several updates.
+    pub fn with_regions(mut self, regions: bool) -> Self {
+        self.settings.regions = regions;
+        self
+    }
+
+    /// Scale every extracted frame up by the given integer factor with nearest neighbor filtering, so low resolution
+    /// pixel art does not need to be scaled when rendering. The frame rectangles still describe the unscaled sheet image.
+    ///
+    /// The frames need factor² times the memory. Panics if the factor is zero.
+    pub fn with_integer_scale(mut self, factor: u32) -> Self {
+        assert!(factor >= 1, "The integer scale of the frames must be at least 1");
+        self.settings.integer_scale = factor;
+        self
+    }
+
+    /// Find the image of an aseprite json file by the path returned by the given resolver, for example to map
+    /// "meta/walk.aseprite.json" to "textures/walk.png" if the json files and images are stored in separate folders.
+    /// The image must be in the same asset source as the json file.
+    ///
+    /// A matcher set with [SpriteSheetPlugin::with_image_matcher] takes precedence over the resolver.
+    pub fn with_image_path_resolver(mut self, resolver: ImagePathResolver) -> Self {
+        self.settings.image_path_resolver = Some(resolver);
+        self
+    }
+
+    /// Load arbitrary json data for every sheet from a file next to its aseprite json file, which has the given file
+    /// ending instead of ".aseprite.json", like "meta.json" for "animation/my_animation.meta.json". The data can be used
+    /// for gameplay data like hitboxes and is available with [SpriteSheet::user_meta].
+    ///
+    /// The files must be loaded before the sheets are created, like the aseprite json files. If several plugins enable
+    /// user meta data, the file ending of the first one is used.
+    pub fn with_user_meta(mut self, extension: &'static str) -> Self {
+        self.settings.user_meta_extension = Some(extension);
+        self
+    }
+
+    /// Make every pixel of the extracted frames transparent which has the given color, ignoring its alpha. This is
+    /// used for sheets which mark the background with a solid color instead of transparency.
+    ///
+    /// The color is compared with the 8 bit srgb values of the pixels, respecting the channel order of
+    /// RGBA and BGRA images. Frames with other formats are left unchanged.
+    ///
+    /// With [`ColorKey::Auto`], the background color stored in the json data of each sheet is used, see
+    /// [`AsepriteData::background_color`]. Sheets without a background color are left unchanged.
+    pub fn with_color_key(mut self, color_key: impl Into<ColorKey>) -> Self {
+        self.settings.color_key = Some(color_key.into());
+        self
+    }
+
+    /// Set the maximum number of tasks used to split a sheet image into its frames in parallel, so creating the
+    /// sheets does not occupy all threads if other work runs at the same time.
+    ///
+    /// Defaults to the number of threads of the compute task pool. A value of 1 splits the frames on the current thread.
+    pub fn with_max_parallelism(mut self, max_parallelism: usize) -> Self {
+        self.settings.max_parallelism = Some(max_parallelism.max(1));
+        self
+    }
+
+    /// Play all animations faster by the given factor, by dividing every frame duration by it. A scale of 2 halves the
+    /// durations, a scale of 0.5 doubles them. The scaled durations are returned by [SpriteSheet::duration_at] and used
+    /// by all functions based on the durations.
+    ///
+    /// Panics if the scale is not a positive number.
+    pub fn with_duration_scale(mut self, scale: f32) -> Self {
+        assert!(scale > 0.0 && scale.is_finite(), "The duration scale must be a positive number, but was {scale}");
+        self.settings.duration_scale = Some(scale);
+        self
+    }
+
+    /// Set the texture dimension of every extracted frame instead of using the dimension of the sheet image, for shaders
+    /// which expect a specific dimension. The extracted frames keep their size and layers.
+    ///
+    /// One dimensional textures must have a height and layer count of one. Frames which are not compatible with the
+    /// dimension keep the dimension of their sheet image and a warning is logged.
+    pub fn with_output_dimension(mut self, dimension: TextureDimension) -> Self {
+        self.settings.output_dimension = Some(dimension);
+        self
+    }
+
+    /// Log a summary when the sheets were created, with the number of sheets, frames and bytes of the frames and how
+    /// long the creation took. Lazy sheets are not included, as they are not created yet.
+    pub fn with_log_summary(mut self, log_summary: bool) -> Self {
+        self.settings.log_summary = log_summary;
+        self
+    }
+
+    /// Set the sampler of every extracted frame, instead of the default sampler of the image plugin.
+    ///
+    /// A sheet can override it with a `"sampler": "nearest"` or `"sampler": "linear"` entry in the meta data of its
+    /// aseprite json file, or the sampler of its sheet definition.
+    pub fn with_sampler(mut self, sampler: ImageSampler) -> Self {
+        self.settings.sampler = Some(sampler);
+        self
+    }
+
+    /// Debug aid: draw a border of one pixel with the given color around every extracted frame, to check that the
+    /// frames are split at the right positions. The border replaces the outer pixels of the frames.
+    ///
+    /// The borders are only drawn in debug builds, so they can not end up in a release by accident. Like the color
+    /// key, they are only drawn on frames with an RGBA or BGRA format.
+    pub fn with_debug_borders(mut self, color: Color) -> Self {
+        self.settings.debug_borders = Some(color);
+        self
+    }
+
+    /// Set what happens when an aseprite json file has no loaded image, like an image which failed to load because
+    /// its file is missing. By default, such sheets are silently not created. The image path and its load error can
+    /// only be reported for pages and with [SpriteSheetPlugin::with_image_path_resolver], as the default matching
+    /// accepts images with any file ending.
+    pub fn with_missing_image(mut self, missing_image: MissingImage) -> Self {
+        self.settings.missing_image = missing_image;
+        self
+    }
+
+    /// Keep all positions derived from the sheets on whole pixels, so pixel art does not shimmer because of sub pixel
+    /// offsets. This affects the accessors which compensate the trim of a frame: [SpriteSheet::pivot_at] and
+    /// `SpriteSheet::sprite_for_frame` (with the `bevy_sprite` feature) move their point to the top left corner of its
+    /// pixel in the untrimmed frame, so the offset to the trimmed frame is a whole number of pixels. For example, the
+    /// center of a frame with an odd width is moved half a pixel to the left.
+    ///
+    /// Frame positions, sizes and trim offsets are always whole pixels, as fractional values in the json data are
+    /// rounded when it is parsed, with a warning.
+    pub fn with_pixel_perfect(mut self, pixel_perfect: bool) -> Self {
+        self.settings.pixel_perfect = pixel_perfect;
+        self
+    }
+
+    /// Use a custom matcher to find the image of an aseprite json file. The first loaded image for which the
+    /// matcher returns true will be used.
+    ///
+    /// By default, the image must have the same asset source, path and file name (without ending) as the json file.
+    pub fn with_image_matcher(mut self, matcher: ImageMatcher) -> Self {
+        self.settings.image_matcher = Some(matcher);
+        self
+    }
+}
+
+/// Decides if the candidate image asset belongs to the aseprite json file with the given path.
+pub type ImageMatcher = fn(json_path: &Path, candidate: &AssetPath) -> bool;
+
+/// Returns the path of the image which belongs to the aseprite json file with the given path.
+pub type ImagePathResolver = fn(json_path: &Path) -> PathBuf;
+
+/// Modifies a frame image after it was extracted from its sheet.
+pub type FramePostprocess = fn(frame: &mut Image);
+
+/// Settings which control how the sprite sheets are created.
+#[derive(Clone, Default)]
+pub(crate) struct SheetSettings {
+    /// If true, a mip chain is generated for every frame
+    mipmaps: bool,
+    /// Custom matcher which pairs the aseprite json files with their images
+    image_matcher: Option<ImageMatcher>,
+    /// Custom resolver which returns the image path of an aseprite json file
+    image_path_resolver: Option<ImagePathResolver>,
+    /// Options used when extracting the frames from the sheet image
+    extract: ExtractOptions,
+    /// If true, the frame ranges of the tags are resolved by the frame names
+    tags_by_frame_name: bool,
+    /// If true, the SpriteSheetAtlases resource is created alongside the sheets
+    atlases: bool,
+    /// The maximum number of pixels of a single frame, DEFAULT_MAX_FRAME_PIXELS if not set
+    max_frame_pixels: Option<usize>,
+    /// The maximum declared width and height of a sheet image, DEFAULT_MAX_SHEET_SIZE if not set
+    max_sheet_size: Option<usize>,
+    /// If set, the sheets are created incrementally with this many frames per update
+    frames_per_tick: Option<usize>,
+    /// If true, images without a matching aseprite json file are logged
+    report_unmatched_images: bool,
+    /// If true, the sheets are only split when they are requested
+    lazy: bool,
+    /// If true, the transparent border of untrimmed frames is removed
+    auto_trim: bool,
+    /// If true, the frame handles of existing sheets are reused when the sheets are created again
+    stable_handles: bool,
+    /// Custom function which is called on every extracted frame
+    frame_postprocess: Option<FramePostprocess>,
+    /// If true, the sheets are not split and only keep the frame rectangles
+    regions: bool,
+    /// The factor every frame is scaled up by, no scaling if 0 or 1
+    integer_scale: u32,
+    /// If set, user meta data is loaded from json files with this file ending
+    user_meta_extension: Option<&'static str>,
+    /// If set, pixels with this color (or the background color of the sheet) are made transparent
+    color_key: Option<ColorKey>,
+    /// The maximum number of tasks used to split a sheet, the thread count of the compute task pool if not set
+    max_parallelism: Option<usize>,
+    /// If set, every frame duration is divided by this factor
+    duration_scale: Option<f32>,
+    /// If set, the dimension of every extracted frame
+    output_dimension: Option<TextureDimension>,
+    /// If true, a summary is logged when the sheets were created
+    log_summary: bool,
+    /// If set, the sampler of every extracted frame, unless its sheet sets its own
+    sampler: Option<ImageSampler>,
+    /// If set, a border with this color is drawn around every extracted frame in debug builds
+    debug_borders: Option<Color>,
+    /// What happens to aseprite json files without a loaded image
+    missing_image: MissingImage,
+    /// If true, the anchors and pivots of the sheets are rounded to whole pixels
+    pixel_perfect: bool,
+}
+
+impl SheetSettings {
+    /// Return the settings used for the sheet with the given data, with an automatic color key resolved to the
+    /// background color of the sheet and the sampler of the sheet, if it sets one.
+    pub(crate) fn for_sheet(&self, data: &AsepriteData) -> SheetSettings {
+        let mut settings = self.clone();
+        if let Some(ColorKey::Auto) = settings.color_key {
+            settings.color_key = data.background_color().map(ColorKey::Color);
+        }
+        if let Some(sampler) = data.meta.sampler {
+            settings.sampler = Some(sampler.to_image_sampler());
+        }
+        settings
+    }
+
+    /// Return true if the created sheets need the handle of their sheet image, which keeps the image loaded.
+    /// Atlases and regions render the frames from the sheet image, while sheets split into frame images do not.
+    pub(crate) fn keeps_source_image(&self) -> bool {
+        self.atlases || self.regions
+    }
+}
+
+/// Defines what happens when an aseprite json file has no loaded image, for example because the image file is
+/// missing or failed to load.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum MissingImage {
+    /// The sheet is not created, without any message. This allows loading the json files before their images.
+    #[default]
+    Ignore,
+    /// The sheet is not created and an error is logged, including the load error of the image if it is known.
+    Skip,
+    /// Panic with a message describing the missing image, so a broken asset can not go unnoticed.
+    Abort,
+}
+
+/// The color which is made transparent in the extracted frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorKey {
+    /// Pixels with this color are made transparent.
+    Color(Color),
+    /// Pixels with the background color stored in the json data of the sheet are made transparent.
+    Auto,
+}
+
+impl From<Color> for ColorKey {
+    fn from(color: Color) -> Self {
+        ColorKey::Color(color)
     }
 }
 
+/// The default maximum number of pixels of a single frame, which is the area of a 8192x8192 image.
+const DEFAULT_MAX_FRAME_PIXELS: usize = 8192 * 8192;
+
+/// The default maximum width and height of a sheet image, which is the largest texture size most desktop GPUs support.
+const DEFAULT_MAX_SHEET_SIZE: usize = 16384;
+
+/// System set containing the system which creates the [SpriteSheets] resource.
+///
+/// The resource is inserted using commands, so it is available to any system in the same schedule
+/// (the OnEnter schedule of the loading state) which is ordered `.after(SpriteSheetSet)`.
+///
+/// This does not hold when the sheets are created incrementally (see [SpriteSheetPlugin::with_frames_per_tick]), as the
+/// resource is only inserted once all frames were created, a few updates later. In lazy mode (see
+/// [SpriteSheetPlugin::with_lazy]), the resource exists, but the sheets must be created with
+/// [SpriteSheets::get_sheet_or_build] before they can be accessed.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpriteSheetSet;
+
 impl <CreateState: States + FreelyMutableState, NextState: States + FreelyMutableState> Plugin for SpriteSheetPlugin<CreateState, NextState> {
     fn build(&self, app: &mut App) {
-        app
-            .add_plugins(JsonAssetPlugin::<AsepriteData>::new(&["aseprite.json"]))
-            .add_systems(
-                OnEnter(self.loading_state.clone()),
-                create_sprite_sheets(self.next_state.clone())
-            )
-        ;
+        // several instances of this plugin can be added, but the aseprite json assets must only be registered once
+        if !app.is_plugin_added::<JsonAssetPlugin<AsepriteData>>() {
+            app
+                .add_plugins(JsonAssetPlugin::<AsepriteData>::new(&[AsepriteData::EXTENSION]))
+                .add_systems(Update, log_aseprite_data_load_failures)
+            ;
+        }
+
+        if let Some(extension) = self.settings.user_meta_extension
+            && !app.is_plugin_added::<JsonAssetPlugin<SheetUserMeta>>() {
+            app.add_plugins(JsonAssetPlugin::<SheetUserMeta>::new(&[extension]));
+        }
+
+        if !app.world().contains_resource::<RebuildSettings>() {
+            app.insert_resource(RebuildSettings(self.settings.clone()));
+        }
+
+        #[cfg(feature = "spritesheet_file")]
+        if !app.is_plugin_added::<crate::sheet_file::SpriteSheetFilePlugin>() {
+            app.add_plugins(crate::sheet_file::SpriteSheetFilePlugin);
+        }
+
+        #[cfg(feature = "ron")]
+        if !app.is_plugin_added::<RonAssetPlugin<SheetDefinition>>() {
+            app.add_plugins(RonAssetPlugin::<SheetDefinition>::new(&[SheetDefinition::EXTENSION]));
+        }
+
+        let stages = [(self.loading_state.clone(), self.next_state.clone())]
+            .into_iter()
+            .chain(self.additional_stages.iter().cloned().map(|(loading_state, next_state)| (loading_state, Some(next_state))));
+
+        for (loading_state, next_state) in stages {
+            app.add_systems(
+                OnEnter(loading_state.clone()),
+                create_sprite_sheets(next_state.clone(), self.settings.clone()).in_set(SpriteSheetSet)
+            );
+
+            if let Some(frames_per_tick) = self.settings.frames_per_tick {
+                app.add_systems(
+                    Update,
+                    create_sprite_sheets_incrementally(next_state, self.settings.clone(), frames_per_tick)
+                        .in_set(SpriteSheetSet)
+                        .run_if(in_state(loading_state).and(resource_exists::<PendingSpriteSheets>))
+                );
+            }
+        }
+    }
+
+    fn is_unique(&self) -> bool {
+        false
+    }
+}
+
+fn create_sprite_sheets<S: States + FreelyMutableState>(followup_state: Option<S>, settings: SheetSettings) -> impl Fn(Commands, Res<AssetServer>, ResMut<Assets<Image>>, Res<Assets<AsepriteData>>, Option<Res<Assets<SheetDefinition>>>, Option<Res<Assets<SheetUserMeta>>>, Option<Res<SpriteSheets>>, ResMut<NextState<S>>, Option<ResMut<Assets<TextureAtlasLayout>>>) {
+    move |mut commands, asset_server, mut images, aseprite_data, sheet_definitions, user_metas, existing_sheets, mut next_state, layouts| {
+        let _span = info_span!("create_sprite_sheets").entered();
+        let started = Instant::now();
+        let matched_sheets = match_loaded_sheets(&asset_server, &mut images, &aseprite_data, sheet_definitions.as_deref(), user_metas.as_deref(), existing_sheets.as_deref(), &settings);
+
+        // in lazy mode, the sheets are only created when requested
+        if settings.lazy {
+            commands.insert_resource(SpriteSheets::new_lazy(matched_sheets, settings.clone()));
+            advance_state(&mut next_state, &followup_state);
+            return
+        }
+
+        // in incremental mode, the sheets are created over the next updates instead
+        if settings.frames_per_tick.is_some() && !settings.regions {
+            start_incremental_creation(&mut commands, matched_sheets, &settings, started);
+            return
+        }
+
+        let sprite_sheets = create_matched_sprite_sheets(matched_sheets, &mut images, &settings);
+        let _span = info_span!("insert_sprite_sheets", sheets = sprite_sheets.path_sheet_map.len()).entered();
+        insert_sprite_sheets(&mut commands, sprite_sheets, &images, &settings, layouts, started);
+        advance_state(&mut next_state, &followup_state)
     }
 }
 
-fn create_sprite_sheets<S: States + FreelyMutableState>(followup_state: S) -> impl Fn(Commands, Res<AssetServer>, ResMut<Assets<Image>>, Res<Assets<AsepriteData>>, ResMut<NextState<S>>) {
-    move |mut commands, asset_server, mut images, aseprite_data, mut next_state| {
-        commands.insert_resource(create_sprite_sheets_from_aseprite_data(
-            &asset_server,
-            &mut images,
-            &aseprite_data,
-        ));
+/// Switch to the state which follows the creation of the sheets, if there is one.
+pub(crate) fn advance_state<S: States + FreelyMutableState>(next_state: &mut NextState<S>, followup_state: &Option<S>) {
+    if let Some(followup_state) = followup_state {
         next_state.set(followup_state.clone())
     }
 }
 
-fn create_sprite_sheets_from_aseprite_data(
+/// The settings of the first added [SpriteSheetPlugin], used by [rebuild_sprite_sheets].
+#[derive(Resource, Clone)]
+pub struct RebuildSettings(SheetSettings);
+
+/// Create a system which replaces the [SpriteSheets] resource with sheets freshly created from the currently loaded
+/// aseprite json and image assets, without any state transition. This is useful after changing the loaded assets
+/// manually, for example in an editor. Run it like any other system, e.g. with a run condition or
+/// `commands.run_system_cached(rebuild_sprite_sheets())`.
+///
+/// The sheets are created with the settings of the first added [SpriteSheetPlugin]. Incremental creation is not used,
+/// so all sheets are created at once, while lazy sheets are only created when they are requested again.
+/// Logs a warning and does nothing if no [SpriteSheetPlugin] was added.
+pub fn rebuild_sprite_sheets() -> impl Fn(Commands, Res<AssetServer>, ResMut<Assets<Image>>, Res<Assets<AsepriteData>>, Option<Res<Assets<SheetDefinition>>>, Option<Res<Assets<SheetUserMeta>>>, Option<Res<SpriteSheets>>, Option<Res<RebuildSettings>>, Option<ResMut<Assets<TextureAtlasLayout>>>) {
+    move |mut commands, asset_server, mut images, aseprite_data, sheet_definitions, user_metas, existing_sheets, settings, layouts| {
+        let Some(settings) = settings else {
+            warn!("The sprite sheets can not be rebuilt, as no SpriteSheetPlugin was added");
+            return
+        };
+
+        let _span = info_span!("rebuild_sprite_sheets").entered();
+        let started = Instant::now();
+        let settings = &settings.0;
+        let matched_sheets = match_loaded_sheets(&asset_server, &mut images, &aseprite_data, sheet_definitions.as_deref(), user_metas.as_deref(), existing_sheets.as_deref(), settings);
+
+        match settings.lazy {
+            true => commands.insert_resource(SpriteSheets::new_lazy(matched_sheets, settings.clone())),
+            false => {
+                let sprite_sheets = create_matched_sprite_sheets(matched_sheets, &mut images, settings);
+                insert_sprite_sheets(&mut commands, sprite_sheets, &images, settings, layouts, started);
+            }
+        }
+    }
+}
+
+/// Pair all loaded aseprite json files and sheet definitions with their images and attach their user meta data.
+fn match_loaded_sheets(
     asset_server: &AssetServer,
     images: &mut Assets<Image>,
     aseprite_data: &Assets<AsepriteData>,
-) -> SpriteSheets {
-    let paths_and_data = aseprite_data
-        .iter()
-        .map(|(id, ad)| (
-            asset_server
-                .get_path(id)
-                .expect("aseprite data should be loaded")
-                .path()
-                .to_str()
-                .expect("path could not be converted to string")
-                .replace("\\", "/")
-                .replace(".aseprite.json", ""),
-            ad
-        ))
-        .filter_map(|(path, ad)| images
-            .iter()
-            // There seems to be an image without a path by default. This call filters it out
-            .filter_map(|(id, image)| match asset_server.get_path(id) {
-                Some(p) => Some((p, image)),
-                None => None
-            })
-            // search the image which has the same path and name as the aseprite descriptor file
-            .find(|(asset_path, _)| asset_path
-                .path()
-                .to_str()
-                .expect("path could not be converted to string")
-                .replace("\\", "/")
-                .split(".")
-                .next()
-                .expect("the image path should have a file ending") == path
-            )
-            .map(|(_, image)| (path, ad, image.clone()))
-        )
-        .collect::<Vec<_>>();
+    sheet_definitions: Option<&Assets<SheetDefinition>>,
+    user_metas: Option<&Assets<SheetUserMeta>>,
+    existing_sheets: Option<&SpriteSheets>,
+    settings: &SheetSettings,
+) -> Vec<MatchedSheet> {
+    let mut sources = collect_sources(asset_server, aseprite_data);
+
+    if let Some(sheet_definitions) = sheet_definitions {
+        sources.extend(collect_sources(asset_server, sheet_definitions));
+    }
+
+    let mut matched_sheets = {
+        let _span = info_span!("match_sheets", sources = sources.len()).entered();
+        match_sheets(asset_server, images, sources, existing_sheets, settings)
+    };
+
+    if let (Some(extension), Some(user_metas)) = (settings.user_meta_extension, user_metas) {
+        attach_user_meta(&mut matched_sheets, asset_server, user_metas, extension);
+    }
+
+    matched_sheets
+}
 
+/// Split all matched sheets at once and collect them into the [SpriteSheets] resource.
+fn create_matched_sprite_sheets(matched_sheets: Vec<MatchedSheet>, images: &mut Assets<Image>, settings: &SheetSettings) -> SpriteSheets {
     SpriteSheets::new(
-        paths_and_data
+        matched_sheets
             .into_iter()
-            .map(|(path, aseprite_data, image)| (
-                path,
-                SpriteSheet::new(
-                    split_image_by_rectangles(&image, aseprite_data.rect_iter())
-                        .into_iter()
-                        .map(|image| images.add(image))
-                )
-            ))
+            .map(|matched| {
+                let _span = info_span!("create_sprite_sheet", sheet = %matched.key).entered();
+                let mut sheet = create_sprite_sheet(&matched.image, matched.source, &matched.data, &matched.previous, images, settings);
+                sheet.source_id = Some(matched.source_id);
+                sheet.source_path = matched.source_path;
+                sheet.user_meta = matched.user_meta;
+                (matched.key, sheet)
+            })
     )
 }
 
-/// Split a given image by the given iterator of rectangles and create sub images from it.
-pub fn split_image_by_rectangles<'a>(image: &'a Image, rectangles: impl IntoIterator<Item=Rect> + 'a) -> impl IntoIterator<Item=Image> + 'a {
-    let dimension = image.texture_descriptor.dimension;
-    let format = image.texture_descriptor.format;
-    let sheet_width = image.texture_descriptor.size.width as usize * format.pixel_size().expect("Could not retrieve pixel size");
+/// Insert the created sprite sheets and, if enabled, their atlases.
+/// If enabled, a summary of the sheets is logged, including the time since the creation started.
+pub(crate) fn insert_sprite_sheets(
+    commands: &mut Commands,
+    sprite_sheets: SpriteSheets,
+    images: &Assets<Image>,
+    settings: &SheetSettings,
+    layouts: Option<ResMut<Assets<TextureAtlasLayout>>>,
+    started: Instant,
+) {
+    if settings.log_summary {
+        info!(
+            "Created {} sprite sheets with {} frames and {} bytes in {:?}",
+            sprite_sheets.path_sheet_map.len(),
+            sprite_sheets.path_sheet_map.values().map(SpriteSheet::len).sum::<usize>(),
+            sprite_sheets.total_byte_size(images),
+            started.elapsed()
+        );
+    }
 
-    rectangles
-        .into_iter()
-        .map(move |rect| {
-            let size = Extent3d {
-                width: rect.width as u32,
-                height: rect.height as u32,
-                depth_or_array_layers: image.texture_descriptor.size.depth_or_array_layers,
-            };
+    if settings.atlases {
+        match layouts {
+            Some(mut layouts) => commands.insert_resource(SpriteSheetAtlases::new(&sprite_sheets, &mut layouts)),
+            None => warn!("The sprite sheet atlases can not be created, as the texture atlas layout assets do not exist")
+        }
+    }
 
-            let image_data = image.data.as_ref().map(|vec| vec.as_slice()).expect("The image should be loaded");
+    commands.insert_resource(sprite_sheets);
+}
 
-            let data = extract_rectangle(image_data, rect, sheet_width, format.pixel_size().expect("Could not retrieve pixel size"));
-            Image::new(
-                size,
-                dimension,
-                data,
-                format,
-                RenderAssetUsages::default()
-            )
+/// Log the path and cause of every aseprite json file which failed to load, for example because it is malformed.
+fn log_aseprite_data_load_failures(mut events: MessageReader<AssetLoadFailedEvent<AsepriteData>>) {
+    for event in events.read() {
+        error!("Failed to load the aseprite json file {}: {}", event.path, event.error);
+    }
+}
+
+/// An aseprite json file paired with its matching image, ready to be split into a sprite sheet.
+pub(crate) struct MatchedSheet {
+    /// The key of the sheet in [SpriteSheets]
+    key: String,
+    data: AsepriteData,
+    /// A copy of the sheet image
+    image: Image,
+    /// The id of the sheet image
+    source_id: AssetId<Image>,
+    /// The handle of the sheet image, only kept if the sheet needs it, see [SheetSettings::keeps_source_image]
+    source: Option<Handle<Image>>,
+    /// The asset path of the sheet image
+    source_path: Option<String>,
+    /// The user meta data of the sheet
+    user_meta: Option<serde_json::Value>,
+    /// The frame handles of the existing sheet with the same key, which are reused for the new frames
+    previous: Vec<Handle<Image>>,
+}
+
+/// Collect the sheet keys, paths and data of all loaded assets of the given source.
+///
+/// Sources with several pages create a sheet for every page, whose key has the index of the page as suffix,
+/// like "animation/my_animation#1". The image of a page is next to the source.
+fn collect_sources<A: SpriteSheetSource>(asset_server: &AssetServer, assets: &Assets<A>) -> Vec<(String, AssetPath<'static>, AsepriteData, Option<PathBuf>)> {
+    assets
+        .iter()
+        .flat_map(|(id, source)| {
+            let path = asset_server.get_path(id).expect("sprite sheet source should be loaded").into_owned();
+            let key = source_sheet_key(&path, A::EXTENSION);
+            let pages = source.pages();
+            let paged = pages.len() > 1;
+
+            pages
+                .into_iter()
+                .enumerate()
+                .map(move |(index, page)| (
+                    match paged {
+                        true => format!("{key}#{index}"),
+                        false => key.clone()
+                    },
+                    path.clone(),
+                    page.data,
+                    page.image.map(|image| path.path().parent().unwrap_or(Path::new("")).join(image))
+                ))
         })
+        .collect()
 }
 
-fn extract_rectangle(data: &[u8], rect: Rect, data_width: usize, pixel_width: usize) -> Vec<u8> {
-    let mut extracted = Vec::with_capacity(rect.width * rect.height);
-    let start_index = data_width * rect.position.y as usize;
+/// Pair every loaded aseprite json file (or other sprite sheet source) with its image.
+fn match_sheets(
+    asset_server: &AssetServer,
+    images: &mut Assets<Image>,
+    mut keys_paths_and_data: Vec<(String, AssetPath<'static>, AsepriteData, Option<PathBuf>)>,
+    existing_sheets: Option<&SpriteSheets>,
+    settings: &SheetSettings,
+) -> Vec<MatchedSheet> {
+    // sort by the json path, so which sheet wins on a key collision is deterministic
+    keys_paths_and_data.sort_by_key(|(_, json_path, _, _)| json_path.to_string());
+
+    let mut key_origins = HashMap::<String, String>::new();
+    let paths_and_data = keys_paths_and_data
+        .into_iter()
+        .filter(|(key, json_path, _, _)| match key_origins.get(key) {
+            Some(first_path) => {
+                warn!("The aseprite files {first_path} and {json_path} both create the sprite sheet {key}. Only {first_path} will be used.");
+                false
+            }
+            None => {
+                key_origins.insert(key.clone(), json_path.to_string());
+                true
+            }
+        })
+        .filter(|(path, _, aseprite_data, _)| declared_size_valid(path, aseprite_data, settings))
+        .filter_map(|(path, json_path, ad, page_image)| {
+            let matched = images
+                .iter()
+                // There seems to be an image without a path by default. This call filters it out
+                .filter_map(|(id, image)| match asset_server.get_path(id) {
+                    Some(p) => Some((p, (id, image))),
+                    None => None
+                })
+                // search the image which matches the aseprite descriptor file
+                .find(|(asset_path, _)| match (&page_image, settings.image_matcher, settings.image_path_resolver) {
+                    // the image of a page is stored in the source itself
+                    (Some(page_image), _, _) => asset_path.source() == json_path.source() && asset_path.path() == page_image,
+                    (None, Some(matcher), _) => matcher(json_path.path(), asset_path),
+                    (None, None, Some(resolver)) => asset_path.source() == json_path.source() && asset_path.path() == resolver(json_path.path()),
+                    // by default, the image must have the same source, path and name as the aseprite descriptor file
+                    (None, None, None) => image_sheet_key(asset_path) == path
+                })
+                .map(|(image_path, (id, image))| (id, image.clone(), image_path.to_string()));
+
+            if matched.is_none() {
+                report_missing_image(asset_server, &path, &json_path, page_image.as_deref(), settings);
+            }
+
+            matched.map(|image| (path, ad, image))
+        })
+        .filter(|(path, aseprite_data, _)| frames_within_size_limit(path, aseprite_data, settings))
+        .filter(|(path, aseprite_data, (_, image, _))| frames_within_image(path, aseprite_data, image))
+        .inspect(|(path, aseprite_data, (_, image, _))| warn_on_size_mismatch(path, aseprite_data, image))
+        .inspect(|(path, aseprite_data, (_, image, _))| warn_on_format_mismatch(path, aseprite_data, image))
+        .map(|(path, aseprite_data, image)| {
+            let aseprite_data = clamp_tags(&path, aseprite_data);
+            (path, aseprite_data, image)
+        })
+        .collect::<Vec<_>>();
 
-    for y in 0..rect.height {
-        let start = start_index + y * data_width + rect.position.x as usize * pixel_width;
-        let end = start + rect.width * pixel_width;
-        data[start..end].into_iter().for_each(|val| extracted.push(*val))
+    if settings.report_unmatched_images {
+        report_unmatched_images(asset_server, images, paths_and_data.iter().map(|(_, _, (id, _, _))| *id).collect());
     }
 
-    extracted
+    paths_and_data
+        .into_iter()
+        .map(|(key, aseprite_data, (source_id, image, source_path))| MatchedSheet {
+            previous: previous_handles(&key, &aseprite_data, existing_sheets, settings),
+            key,
+            data: match settings.auto_trim {
+                true => auto_trim(&aseprite_data, &image, settings.extract),
+                false => aseprite_data
+            },
+            image,
+            source_id,
+            // lazy sheets are created later, so they need the image until then
+            source: match settings.keeps_source_image() || settings.lazy {
+                true => images.get_strong_handle(source_id),
+                false => None
+            },
+            source_path: Some(source_path),
+            user_meta: None,
+        })
+        .collect()
 }
 
-/// Collection of all existing sprite sheets.
-/// As these sprite sheets aren't assets themself, they are stored in here instead of Assets.
-#[derive(Resource)]
-pub struct SpriteSheets {
-    path_sheet_map: HashMap<String, SpriteSheet>,
-}
+/// Return the frame handles of the existing sheet with the given key if stable handles are enabled and the
+/// sheet still has the same number of frames.
+fn previous_handles(key: &str, aseprite_data: &AsepriteData, existing_sheets: Option<&SpriteSheets>, settings: &SheetSettings) -> Vec<Handle<Image>> {
+    if !settings.stable_handles {
+        return Vec::new()
+    }
 
-impl SpriteSheets {
-    pub(crate) fn new(paths_and_sheets: impl IntoIterator<Item=(String, SpriteSheet)>) -> Self {
-        SpriteSheets {
-            path_sheet_map: paths_and_sheets.into_iter().collect()
+    match existing_sheets.and_then(|sheets| sheets.path_sheet_map.get(key)) {
+        Some(sheet) if sheet.textures.len() == aseprite_data.frames.len() => sheet.textures.clone(),
+        Some(sheet) => {
+            warn!("The frame handles of the sprite sheet {key} can not be reused, as its frame count changed from {} to {}", sheet.len(), aseprite_data.frames.len());
+            Vec::new()
         }
+        None => Vec::new()
     }
+}
 
-    /// Return the sheet specified by the given path.
-    ///
-    /// The path should have no file ending, so if you have an asset "animation/my_animation.png" as a sheet
-    /// and a "animation/my_animation.aseprite.json" aseprite file, you need to provide
-    /// "animation/my_animation" as parameter
-    pub fn get_sheet(&self, path: &str) -> &SpriteSheet {
-        self.path_sheet_map.get(path).expect(&format!("sprite sheet {path} was not loaded!"))
+/// Add the given frame image to the image assets. If a previous handle is given and its image still exists, the
+/// image is replaced in place instead, so the handle stays valid.
+pub(crate) fn add_frame(images: &mut Assets<Image>, frame: Image, previous: Option<&Handle<Image>>) -> Handle<Image> {
+    if let Some(handle) = previous && let Some(image) = images.get_mut(handle) {
+        *image = frame;
+        return handle.clone()
     }
+
+    images.add(frame)
 }
 
-/// Stores handles to image parts from a bigger sprite sheet image.
-pub struct SpriteSheet {
-    pub textures: Vec<Handle<Image>>,
+/// Handle an aseprite json file without a loaded image as configured with [SpriteSheetPlugin::with_missing_image].
+/// If the path of the image is known, the message contains its load state, like the error it failed to load with.
+fn report_missing_image(asset_server: &AssetServer, key: &str, json_path: &AssetPath, page_image: Option<&Path>, settings: &SheetSettings) {
+    if settings.missing_image == MissingImage::Ignore {
+        return
+    }
+
+    let image_path = page_image
+        .map(Path::to_path_buf)
+        .or_else(|| match settings.image_matcher {
+            Some(_) => None,
+            None => settings.image_path_resolver.map(|resolver| resolver(json_path.path()))
+        })
+        .map(|path| AssetPath::from_path_buf(path).with_source(json_path.source().clone_owned()));
+
+    let cause = match image_path {
+        Some(image_path) => match asset_server.get_path_id(image_path.clone()).and_then(|id| asset_server.get_load_state(id)) {
+            Some(LoadState::Failed(error)) => format!("its image {image_path} failed to load: {error}"),
+            Some(_) => format!("its image {image_path} is not loaded"),
+            None => format!("its image {image_path} was never loaded")
+        },
+        None => "no loaded image matches it".to_string()
+    };
+
+    match settings.missing_image {
+        MissingImage::Ignore => {}
+        MissingImage::Skip => error!("The sprite sheet {key} from {json_path} is skipped, as {cause}"),
+        MissingImage::Abort => panic!("The sprite sheet {key} from {json_path} can not be created, as {cause}"),
+    }
 }
 
-impl SpriteSheet {
-    pub(crate) fn new(handles: impl IntoIterator<Item=Handle<Image>>) -> Self {
-        Self {
-            textures: handles.into_iter().collect()
+/// Log every image with a path which does not belong to any of the matched sheets.
+fn report_unmatched_images(asset_server: &AssetServer, images: &Assets<Image>, matched: HashSet<AssetId<Image>>) {
+    images
+        .ids()
+        .filter(|id| !matched.contains(id))
+        .filter_map(|id| asset_server.get_path(id))
+        .for_each(|path| info!("The image {path} has no matching aseprite json file"))
+}
+
+/// Check that the sheet size declared in the meta data of the given aseprite data is neither empty nor larger than
+/// allowed, which usually means the json file is corrupt. Logs an error otherwise.
+fn declared_size_valid(path: &str, aseprite_data: &AsepriteData, settings: &SheetSettings) -> bool {
+    let max_size = settings.max_sheet_size.unwrap_or(DEFAULT_MAX_SHEET_SIZE);
+    let Some(size) = aseprite_data.meta.size else {
+        return true
+    };
+
+    match (size.w == 0 || size.h == 0, size.w > max_size || size.h > max_size) {
+        (true, _) => error!("The sprite sheet {path} was not created, as its aseprite data declares an empty image of {}x{}", size.w, size.h),
+        (_, true) => error!("The sprite sheet {path} was not created, as its aseprite data declares an image of {}x{}, which is larger than {max_size}x{max_size}", size.w, size.h),
+        (false, false) => return true
+    }
+
+    false
+}
+
+/// Check that no frame of the given aseprite data has more pixels than allowed, so untrusted json files
+/// can not exhaust the memory with huge frames. Logs an error if the limit is exceeded.
+fn frames_within_size_limit(path: &str, aseprite_data: &AsepriteData, settings: &SheetSettings) -> bool {
+    let max_pixels = settings.max_frame_pixels.unwrap_or(DEFAULT_MAX_FRAME_PIXELS);
+
+    match aseprite_data.rect_iter().into_iter().find(|rect| rect.width.saturating_mul(rect.height) > max_pixels) {
+        Some(rect) => {
+            error!("The sprite sheet {path} was not created, as its frame {rect:?} has more than {max_pixels} pixels");
+            false
         }
+        None => true
     }
+}
 
-    pub fn image_at(&self, index: usize) -> Handle<Image> {
-        self.textures[index].clone()
+/// Check that all frames of the given aseprite data can be extracted from the image. Logs an error for every
+/// invalid frame otherwise.
+fn frames_within_image(path: &str, aseprite_data: &AsepriteData, image: &Image) -> bool {
+    let rects = aseprite_data.rect_iter().into_iter().collect::<Vec<_>>();
+
+    match validate_rects(image, &rects) {
+        Ok(_) => true,
+        Err(errors) => {
+            errors.iter().for_each(|e| error!("The sprite sheet {path} was not created: {e}"));
+            false
+        }
     }
+}
 
-    pub fn images_at(&self, indexes: impl IntoIterator<Item=usize>) -> Vec<Handle<Image>> {
-        indexes.into_iter().map(|i| self.textures[i].clone()).collect()
+/// Clamp the frame ranges of all tags to the frames of the sheet, so they can not select missing frames.
+/// Logs a warning for every clamped tag. Tags of sheets without any frame are removed.
+pub(crate) fn clamp_tags(path: &str, mut aseprite_data: AsepriteData) -> AsepriteData {
+    let invalid_tags = aseprite_data.invalid_tags();
+    if invalid_tags.is_empty() {
+        return aseprite_data
+    }
+
+    let frame_count = aseprite_data.frames.len();
+    for tag in invalid_tags {
+        warn!("The tag {} of the sprite sheet {path} selects the frames {} to {}, but the sheet only has {frame_count} frames", tag.name, tag.from, tag.to);
+    }
+
+    match frame_count {
+        0 => aseprite_data.meta.frame_tags.clear(),
+        _ => aseprite_data.meta.frame_tags.iter_mut().for_each(|tag| {
+            tag.from = tag.from.min(frame_count - 1);
+            tag.to = tag.to.min(frame_count - 1);
+        })
+    }
+
+    aseprite_data
+}
+
+/// Log a warning if the sheet size stored in the aseprite data differs from the size of the image, which usually means
+/// the image was exported again, for example with a different scale, without updating the json file.
+fn warn_on_size_mismatch(path: &str, aseprite_data: &AsepriteData, image: &Image) {
+    let image_size = image.size();
+
+    if let Some(size) = mismatched_size(aseprite_data, image_size) {
+        warn!("The aseprite data of the sprite sheet {path} describes a {}x{} image, but the image is {}x{}", size.w, size.h, image_size.x, image_size.y);
     }
-}
\ No newline at end of file
+}
+
+/// Return the sheet size stored in the aseprite data if it differs from the given image size.
+fn mismatched_size(aseprite_data: &AsepriteData, image_size: UVec2) -> Option<Size> {
+    aseprite_data.meta.size.filter(|size| size.w as u32 != image_size.x || size.h as u32 != image_size.y)
+}
+
+/// Log a warning if the pixel format declared in the aseprite data does not fit the format of the loaded image.
+/// Indexed images ("I8") are usually expanded to RGBA when they are loaded, so both fit. Unknown formats are not checked.
+fn warn_on_format_mismatch(path: &str, aseprite_data: &AsepriteData, image: &Image) {
+    let format = image.texture_descriptor.format;
+
+    if !format_fits(aseprite_data.declared_format(), format) {
+        warn!(
+            "The aseprite data of the sprite sheet {path} declares the format {}, but the image was loaded with the format {format:?}",
+            aseprite_data.declared_format().unwrap_or_default()
+        );
+    }
+}
+
+/// Return true if an image with the given format can hold the pixels of the declared format, see [warn_on_format_mismatch].
+fn format_fits(declared_format: Option<&str>, format: TextureFormat) -> bool {
+    let rgba = ChannelLayout::of(format).is_some();
+
+    match declared_format {
+        Some("RGBA8888") => rgba,
+        Some("I8") => rgba || format.pixel_size().is_ok_and(|size| size == 1),
+        _ => true
+    }
+}
+
+/// Crop every untrimmed frame of the given aseprite data to the opaque pixels of the image and store the
+/// applied offset as trim information. Frames of images without an 8 bit alpha channel are left unchanged.
+fn auto_trim(aseprite_data: &AsepriteData, image: &Image, options: ExtractOptions) -> AsepriteData {
+    let mut trimmed_data = aseprite_data.clone();
+    let (Some(layout), Some(data)) = (ChannelLayout::of(image.texture_descriptor.format), image.data.as_ref()) else {
+        return trimmed_data
+    };
+    let stride = row_stride(image);
+
+    for frame_value in trimmed_data.frames.values_mut().filter(|frame_value| !frame_value.trimmed) {
+        let frame = frame_value.frame;
+        let rect = Rect::new(p!(frame.x, frame.y), frame.w, frame.h);
+        // fully transparent frames keep a single pixel, as images can not be empty
+        let bounds = crate::core::opaque_bounds(data, rect, stride, layout.channels, layout.alpha_offset())
+            .unwrap_or(Rect::new(p!(frame.x, frame.y), 1, 1));
+
+        let offset_x = match options.flip_x != frame_value.flip_x {
+            // mirrored frames are cropped from the right of their rectangle in the sheet, as their columns are reversed
+            true => frame.x + frame.w - (bounds.position.x as usize + bounds.width),
+            false => bounds.position.x as usize - frame.x
+        };
+        let offset_y = match options.flip_y != frame_value.flip_y {
+            // flipped frames are cropped from the bottom of their rectangle in the sheet, as their rows are reversed
+            true => frame.y + frame.h - (bounds.position.y as usize + bounds.height),
+            false => bounds.position.y as usize - frame.y
+        };
+
+        frame_value.frame = Frame { x: bounds.position.x as usize, y: bounds.position.y as usize, w: bounds.width, h: bounds.height };
+        frame_value.trimmed = true;
+        frame_value.sprite_source_size = Some(Frame { x: offset_x, y: offset_y, w: bounds.width, h: bounds.height });
+        frame_value.source_size = Some(frame_value.source_size.unwrap_or(Size { w: frame.w, h: frame.h }));
+    }
+
+    trimmed_data
+}
+
+/// Create the sprite sheets from the given keys, sheet images and aseprite data, without the asset server.
+/// No images are matched, so this is useful for tests and procedurally generated sheets. The frames are added to
+/// the given image assets and the sheets can be retrieved with their key from the returned [SpriteSheets].
+///
+/// Sheets with frames outside of their image are not created and an error is logged.
+pub fn build_sprite_sheets_from(sheets: impl IntoIterator<Item=(String, Image, AsepriteData)>, images: &mut Assets<Image>) -> SpriteSheets {
+    let settings = SheetSettings::default();
+
+    SpriteSheets::new(
+        sheets
+            .into_iter()
+            .filter(|(key, image, aseprite_data)| frames_within_image(key, aseprite_data, image))
+            .map(|(key, image, aseprite_data)| {
+                let aseprite_data = clamp_tags(&key, aseprite_data);
+                let sheet = create_sprite_sheet(&image, None, &aseprite_data, &[], images, &settings);
+                (key, sheet)
+            })
+    )
+}
+
+/// Create a single sprite sheet by splitting its image and adding the frames to the image assets.
+fn create_sprite_sheet(
+    image: &Image,
+    source: Option<Handle<Image>>,
+    aseprite_data: &AsepriteData,
+    previous: &[Handle<Image>],
+    images: &mut Assets<Image>,
+    settings: &SheetSettings,
+) -> SpriteSheet {
+    // in region mode, the frames are only described by their rectangles in the sheet image
+    if settings.regions {
+        return finish_sprite_sheet(Vec::new(), source, image.size(), aseprite_data.clone(), settings)
+    }
+
+    let settings = &settings.for_sheet(aseprite_data);
+    let frames = {
+        let _span = debug_span!("split_image", frames = aseprite_data.frames.len()).entered();
+        extract_frames(image, frame_rects(aseprite_data, settings.extract), settings)
+    };
+
+    let handles = {
+        let _span = debug_span!("add_frame_images", frames = frames.len()).entered();
+        frames
+            .into_iter()
+            .enumerate()
+            .map(|(i, image)| add_frame(images, image, previous.get(i)))
+            .collect::<Vec<_>>()
+    };
+
+    finish_sprite_sheet(
+        handles,
+        source,
+        image.size(),
+        aseprite_data.clone(),
+        settings
+    )
+}
+
+/// Return the rectangles of all frames of the given aseprite data with the options to extract each of them. Frames
+/// which are stored mirrored in the sheet image are flipped back.
+pub(crate) fn frame_rects(aseprite_data: &AsepriteData, options: ExtractOptions) -> Vec<(Rect, ExtractOptions)> {
+    aseprite_data.rect_iter()
+        .into_iter()
+        .zip(aseprite_data.frame_infos())
+        .map(|(rect, frame)| (rect, ExtractOptions {
+            flip_x: options.flip_x != frame.flip_x,
+            flip_y: options.flip_y != frame.flip_y,
+        }))
+        .collect()
+}
+
+/// Split the given rectangles out of the sheet image and process the frames. The frames are split in parallel on
+/// the compute task pool, using at most the configured number of tasks.
+fn extract_frames(image: &Image, rects: Vec<(Rect, ExtractOptions)>, settings: &SheetSettings) -> Vec<Image> {
+    let extract = |rects: &[(Rect, ExtractOptions)]| rects
+        .iter()
+        .flat_map(|&(rect, options)| split_image(image, [rect], options))
+        .map(|frame| process_frame(frame, settings))
+        .collect::<Vec<_>>();
+
+    let Some(pool) = ComputeTaskPool::try_get() else {
+        return extract(&rects)
+    };
+
+    let parallelism = settings.max_parallelism
+        .unwrap_or(pool.thread_num())
+        .clamp(1, rects.len().max(1));
+
+    if parallelism == 1 {
+        return extract(&rects)
+    }
+
+    // the frames of each chunk are returned in the order the tasks were spawned, so the frame order is kept
+    pool
+        .scope(|scope| {
+            for chunk in rects.chunks(rects.len().div_ceil(parallelism)) {
+                scope.spawn(async move { extract(chunk) });
+            }
+        })
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Create the sprite sheet from the already added frame images.
+pub(crate) fn finish_sprite_sheet(
+    handles: impl IntoIterator<Item=Handle<Image>>,
+    source: Option<Handle<Image>>,
+    source_size: UVec2,
+    aseprite_data: AsepriteData,
+    settings: &SheetSettings,
+) -> SpriteSheet {
+    let mut sheet = SpriteSheet::new(handles, source, source_size, aseprite_data);
+
+    if settings.tags_by_frame_name {
+        sheet.resolve_animations_by_frame_name();
+    }
+
+    if let Some(scale) = settings.duration_scale {
+        sheet.frames.iter_mut().for_each(|frame| frame.duration = frame.duration.div_f32(scale));
+    }
+
+    sheet.pixel_perfect = settings.pixel_perfect;
+    sheet
+}
+
+/// Create the sheet key for the asset path of a sprite sheet source, like an aseprite json file.
+/// The file ending of the source, like ".aseprite.json", is removed.
+pub(crate) fn source_sheet_key(asset_path: &AssetPath, extension: &str) -> String {
+    sheet_key(asset_path, &normalized_path(asset_path).replace(&format!(".{extension}"), ""))
+}
+
+/// Create the sheet key for an image asset path. Everything after the first "." is removed.
+fn image_sheet_key(asset_path: &AssetPath) -> String {
+    sheet_key(
+        asset_path,
+        normalized_path(asset_path)
+            .split(".")
+            .next()
+            .expect("the image path should have a file ending")
+    )
+}
+
+/// Prefix the given path with the asset source of the given asset path, like "embedded://animation/my_animation".
+/// Assets from the default source are not prefixed.
+fn sheet_key(asset_path: &AssetPath, path: &str) -> String {
+    match asset_path.source().as_str() {
+        Some(source) => format!("{source}://{path}"),
+        None => path.to_string()
+    }
+}
+
+fn normalized_path(asset_path: &AssetPath) -> String {
+    asset_path
+        .path()
+        .to_str()
+        .expect("path could not be converted to string")
+        .replace("\\", "/")
+}
+
+/// Split a given image by the given iterator of rectangles and create sub images from it.
+pub fn split_image_by_rectangles<'a>(image: &'a Image, rectangles: impl IntoIterator<Item=Rect> + 'a) -> impl IntoIterator<Item=Image> + 'a {
+    split_image(image, rectangles, ExtractOptions::default())
+}
+
+/// Split a given image, which is a uniform grid of frames, into the given number of frames. The size of the frames is
+/// derived from the image size, the number of columns and the number of rows needed for the frames. The frames are
+/// ordered row by row, so the last row might only be partially used.
+///
+/// Panics if columns is zero or the image width and height can not be divided evenly by the columns and rows.
+pub fn split_image_by_count(image: &Image, frame_count: usize, columns: usize) -> Vec<Image> {
+    let size = image.texture_descriptor.size;
+    let (width, height) = (size.width as usize, size.height as usize);
+
+    assert!(columns > 0, "The image can not be split into zero columns");
+    assert!(width % columns == 0, "The image width {width} can not be divided evenly into {columns} columns");
+
+    let rows = frame_count.div_ceil(columns).max(1);
+    assert!(height % rows == 0, "The image height {height} can not be divided evenly into {rows} rows");
+
+    let (frame_width, frame_height) = (width / columns, height / rows);
+    let rects = (0..frame_count)
+        .map(|i| Rect::new(p!((i % columns) * frame_width, (i / columns) * frame_height), frame_width, frame_height))
+        .collect::<Vec<_>>();
+
+    split_image_by_rectangles(image, rects).into_iter().collect()
+}
+
+/// Split a given image, which is a single row or column of frames, into the given number of frames of equal size.
+/// If horizontal is true, the frames are placed side by side from left to right and have the height of the image.
+/// Otherwise, they are stacked from top to bottom and have the width of the image.
+///
+/// Panics if the frame count is zero or the image can not be divided evenly along the axis.
+pub fn split_image_into_strip(image: &Image, frame_count: usize, horizontal: bool) -> Vec<Image> {
+    let size = image.texture_descriptor.size;
+    let (width, height) = (size.width as usize, size.height as usize);
+
+    assert!(frame_count > 0, "The image can not be split into zero frames");
+    let length = match horizontal {
+        true => width,
+        false => height,
+    };
+    assert!(length % frame_count == 0, "The image length {length} can not be divided evenly into {frame_count} frames");
+
+    let frame_length = length / frame_count;
+    let rects = (0..frame_count)
+        .map(|i| match horizontal {
+            true => Rect::new(p!(i * frame_length, 0), frame_length, height),
+            false => Rect::new(p!(0, i * frame_length), width, frame_length),
+        })
+        .collect::<Vec<_>>();
+
+    split_image_by_rectangles(image, rects).into_iter().collect()
+}
+
+/// Check that all given rectangles have an area and lie within the given image. Returns every offending rectangle.
+pub fn validate_rects(image: &Image, rects: &[Rect]) -> Result<(), Vec<RectError>> {
+    let size = image.texture_descriptor.size;
+    crate::core::validate_rects(size.width as usize, size.height as usize, rects)
+}
+
+/// Split a given image by the given rectangles like [split_image_by_rectangles], but validate all rectangles
+/// first instead of panicking on an invalid one.
+pub fn try_split_image_by_rectangles(image: &Image, rectangles: impl IntoIterator<Item=Rect>) -> Result<Vec<Image>, Vec<RectError>> {
+    let rects = rectangles.into_iter().collect::<Vec<_>>();
+    validate_rects(image, &rects)?;
+    Ok(split_image_by_rectangles(image, rects).into_iter().collect())
+}
+
+pub(crate) fn split_image<'a>(image: &'a Image, rectangles: impl IntoIterator<Item=Rect> + 'a, options: ExtractOptions) -> impl IntoIterator<Item=Image> + 'a {
+    let dimension = image.texture_descriptor.dimension;
+    let format = image.texture_descriptor.format;
+    let sheet_width = row_stride(image);
+
+    rectangles
+        .into_iter()
+        .map(move |rect| {
+            let size = Extent3d {
+                width: rect.width as u32,
+                height: rect.height as u32,
+                depth_or_array_layers: image.texture_descriptor.size.depth_or_array_layers,
+            };
+
+            let image_data = image.data.as_ref().map(|vec| vec.as_slice()).expect("The image should be loaded");
+
+            let data = crate::core::extract_rectangle(image_data, rect, sheet_width, format.pixel_size().expect("Could not retrieve pixel size"), options);
+            Image::new(
+                size,
+                dimension,
+                data,
+                format,
+                RenderAssetUsages::default()
+            )
+        })
+}
+
+/// Rotate the given image clockwise by the given number of quarter turns. The width and height are swapped for an odd
+/// number of turns.
+pub fn rotate_image_90(image: &Image, quarter_turns: u8) -> Image {
+    let format = image.texture_descriptor.format;
+    let size = image.texture_descriptor.size;
+    let (data, width, height) = crate::core::rotate_90(
+        image.data.as_ref().map(|vec| vec.as_slice()).expect("The image should be loaded"),
+        size.width as usize,
+        size.height as usize,
+        format.pixel_size().expect("Could not retrieve pixel size"),
+        quarter_turns,
+    );
+
+    let mut rotated = Image::new(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: size.depth_or_array_layers,
+        },
+        image.texture_descriptor.dimension,
+        data,
+        format,
+        image.asset_usage
+    );
+    rotated.sampler = image.sampler.clone();
+    rotated
+}
+
+/// Set the texture dimension of the given image, if its size is compatible with the dimension.
+fn set_dimension(image: &mut Image, dimension: TextureDimension) {
+    let size = image.texture_descriptor.size;
+    let compatible = match dimension {
+        TextureDimension::D1 => size.height == 1 && size.depth_or_array_layers == 1,
+        TextureDimension::D2 | TextureDimension::D3 => true,
+    };
+
+    match compatible {
+        true => image.texture_descriptor.dimension = dimension,
+        false => warn!("A frame of size {}x{}x{} can not have the dimension {dimension:?}", size.width, size.height, size.depth_or_array_layers)
+    }
+}
+
+/// Make every pixel of the given image with the given color transparent, in the channel order of the image format.
+fn apply_color_key(image: &mut Image, color: Color) {
+    let format = image.texture_descriptor.format;
+
+    let Some(layout) = ChannelLayout::of(format) else {
+        warn!("The color key can not be applied to images with format {format:?}");
+        return
+    };
+
+    let key = layout.to_pixel(color.to_srgba().to_u8_array());
+    if let Some(data) = image.data.as_mut() {
+        crate::core::apply_color_key(data, layout.channels, &key, layout.alpha_offset());
+    }
+}
+
+/// Draw a border of one pixel with the given color around the given image, in the channel order of the image format.
+fn draw_debug_border(image: &mut Image, color: Color) {
+    let format = image.texture_descriptor.format;
+    let size = image.texture_descriptor.size;
+
+    let Some(layout) = ChannelLayout::of(format) else {
+        warn!("Debug borders can not be drawn on images with format {format:?}");
+        return
+    };
+
+    let pixel = layout.to_pixel(color.to_srgba().to_u8_array());
+    if let Some(data) = image.data.as_mut() {
+        crate::core::draw_border(data, size.width as usize, size.height as usize, layout.channels, &pixel[..layout.channels]);
+    }
+}
+
+/// Scale the given image up by the given integer factor with nearest neighbor filtering.
+fn scale_image(image: &Image, factor: u32) -> Image {
+    let format = image.texture_descriptor.format;
+    let size = image.texture_descriptor.size;
+    let data = crate::core::scale_nearest(
+        image.data.as_ref().map(|vec| vec.as_slice()).expect("The image should be loaded"),
+        size.width as usize,
+        size.height as usize * size.depth_or_array_layers as usize,
+        format.pixel_size().expect("Could not retrieve pixel size"),
+        factor as usize,
+    );
+
+    let mut scaled = Image::new(
+        Extent3d {
+            width: size.width * factor,
+            height: size.height * factor,
+            depth_or_array_layers: size.depth_or_array_layers,
+        },
+        image.texture_descriptor.dimension,
+        data,
+        format,
+        image.asset_usage
+    );
+    scaled.sampler = image.sampler.clone();
+    scaled
+}
+
+/// Return the number of bytes of one row in the image data.
+///
+/// Image data is usually tightly packed, but rows might be padded to meet alignment requirements.
+/// In this case, the data contains more bytes than the rows need and the stride is derived from its length.
+/// Images with a mip chain are assumed to be tightly packed, as the additional bytes belong to the mip levels.
+fn row_stride(image: &Image) -> usize {
+    let size = image.texture_descriptor.size;
+    let tight_width = size.width as usize * image.texture_descriptor.format.pixel_size().expect("Could not retrieve pixel size");
+    let rows = size.height as usize * size.depth_or_array_layers as usize;
+    let data_len = image.data.as_ref().map(|data| data.len()).unwrap_or_default();
+
+    match rows {
+        0 => tight_width,
+        _ if image.texture_descriptor.mip_level_count > 1 => tight_width,
+        _ if data_len > tight_width * rows && data_len % rows == 0 => data_len / rows,
+        _ => tight_width
+    }
+}
+
+/// Apply the settings to a frame image which was split from its sheet.
+pub(crate) fn process_frame(mut image: Image, settings: &SheetSettings) -> Image {
+    if let Some(dimension) = settings.output_dimension {
+        set_dimension(&mut image, dimension);
+    }
+
+    if let Some(ColorKey::Color(color)) = settings.color_key {
+        apply_color_key(&mut image, color);
+    }
+
+    if settings.integer_scale > 1 {
+        image = scale_image(&image, settings.integer_scale);
+    }
+
+    if let Some(color) = settings.debug_borders
+        && cfg!(debug_assertions) {
+        draw_debug_border(&mut image, color);
+    }
+
+    if let Some(sampler) = &settings.sampler {
+        image.sampler = sampler.clone();
+    }
+
+    if let Some(postprocess) = settings.frame_postprocess {
+        postprocess(&mut image);
+    }
+
+    if settings.mipmaps {
+        generate_mipmaps(&mut image);
+    }
+
+    image
+}
+
+/// Generate a full mip chain for the given image by repeatedly halving it with a box filter.
+/// The mip levels are appended to the image data and the mip level count is set accordingly.
+///
+/// Only formats with four 8 bit channels are supported. Other images are left unchanged.
+fn generate_mipmaps(image: &mut Image) {
+    let format = image.texture_descriptor.format;
+
+    let Some(layout) = ChannelLayout::of(format) else {
+        warn!("Mipmaps can not be generated for images with format {format:?}");
+        return
+    };
+
+    let pixel_width = layout.channels;
+    let width = image.texture_descriptor.size.width as usize;
+    let height = image.texture_descriptor.size.height as usize;
+    let data = image.data.take().expect("The image should be loaded");
+    let (data, level_count) = crate::core::mip_chain(data, width, height, pixel_width);
+
+    image.data = Some(data);
+    image.texture_descriptor.mip_level_count = level_count;
+}
+
+/// Collection of all existing sprite sheets.
+/// As these sprite sheets aren't assets themself, they are stored in here instead of Assets.
+#[derive(Resource)]
+pub struct SpriteSheets {
+    path_sheet_map: HashMap<String, SpriteSheet>,
+    /// In lazy mode, the sheets which were not yet created
+    unbuilt: HashMap<String, UnbuiltSheet>,
+    /// The settings used to create the unbuilt sheets
+    settings: SheetSettings,
+}
+
+/// A sheet whose aseprite data was matched with its image, but which is only split when it is requested.
+struct UnbuiltSheet {
+    data: AsepriteData,
+    source: Handle<Image>,
+    source_path: Option<String>,
+    user_meta: Option<serde_json::Value>,
+}
+
+impl SpriteSheets {
+    /// Create the sheets from the given paths and sheets. If a path occurs more than once, the first sheet is kept.
+    pub(crate) fn new(paths_and_sheets: impl IntoIterator<Item=(String, SpriteSheet)>) -> Self {
+        let mut path_sheet_map = HashMap::new();
+
+        for (path, sheet) in paths_and_sheets {
+            if path_sheet_map.contains_key(&path) {
+                warn!("The sprite sheet {path} was created more than once. Only the first one will be used.");
+                continue
+            }
+
+            path_sheet_map.insert(path, sheet);
+        }
+
+        SpriteSheets {
+            path_sheet_map,
+            unbuilt: HashMap::new(),
+            settings: SheetSettings::default(),
+        }
+    }
+
+    /// Create the sheets in lazy mode. The matched sheets are only split when requested with [SpriteSheets::get_sheet_or_build].
+    pub(crate) fn new_lazy(matched_sheets: impl IntoIterator<Item=MatchedSheet>, settings: SheetSettings) -> Self {
+        SpriteSheets {
+            path_sheet_map: HashMap::new(),
+            unbuilt: matched_sheets
+                .into_iter()
+                .filter_map(|matched| Some((matched.key, UnbuiltSheet { data: matched.data, source: matched.source?, source_path: matched.source_path, user_meta: matched.user_meta })))
+                .collect(),
+            settings,
+        }
+    }
+
+    /// Return the sheet specified by the given path, creating it first if it was not created yet.
+    ///
+    /// This is required in lazy mode (see [SpriteSheetPlugin::with_lazy]), where sheets are only split into their
+    /// frames when they are requested for the first time. As this might create the sheet, it needs mutable access to
+    /// the sheets and the image assets. Returns None if no sheet with this path exists or its image is not loaded.
+    pub fn get_sheet_or_build(&mut self, images: &mut Assets<Image>, path: &str) -> Option<&SpriteSheet> {
+        if let Some(unbuilt) = self.unbuilt.remove(path) {
+            let Some(image) = images.get(&unbuilt.source).cloned() else {
+                self.unbuilt.insert(path.to_string(), unbuilt);
+                return None
+            };
+
+            let source_id = unbuilt.source.id();
+            let source = self.settings.keeps_source_image().then_some(unbuilt.source);
+            let mut sheet = create_sprite_sheet(&image, source, &unbuilt.data, &[], images, &self.settings);
+            sheet.source_id = Some(source_id);
+            sheet.source_path = unbuilt.source_path;
+            sheet.user_meta = unbuilt.user_meta;
+            self.path_sheet_map.insert(path.to_string(), sheet);
+        }
+
+        self.path_sheet_map.get(path)
+    }
+
+    /// Return the sheet specified by the given path.
+    /// In lazy mode, sheets which were not requested with [SpriteSheets::get_sheet_or_build] yet are not available.
+    ///
+    /// The path should have no file ending, so if you have an asset "animation/my_animation.png" as a sheet
+    /// and a "animation/my_animation.aseprite.json" aseprite file, you need to provide
+    /// "animation/my_animation" as parameter.
+    ///
+    /// Sheets loaded from a non default asset source are prefixed with their source, like
+    /// "embedded://animation/my_animation".
+    pub fn get_sheet(&self, path: &str) -> &SpriteSheet {
+        self.path_sheet_map.get(path).expect(&format!("sprite sheet {path} was not loaded!"))
+    }
+
+    /// Check that the sheet with the given path has the expected number of frames, for games which rely on a fixed
+    /// layout of their sheets. Unbuilt lazy sheets are checked by the number of frames in their data.
+    pub fn expect_frame_count(&self, path: &str, expected: usize) -> Result<(), FrameCountMismatch> {
+        let actual = match (self.path_sheet_map.get(path), self.unbuilt.get(path)) {
+            (Some(sheet), _) => sheet.len(),
+            (None, Some(unbuilt)) => unbuilt.data.frames.len(),
+            (None, None) => return Err(FrameCountMismatch::Missing { path: path.to_string(), expected })
+        };
+
+        match actual == expected {
+            true => Ok(()),
+            false => Err(FrameCountMismatch::Different { path: path.to_string(), expected, actual })
+        }
+    }
+
+    /// Iterate over all sheets and their paths, sorted by the path.
+    pub fn iter_sorted(&self) -> impl Iterator<Item=(&str, &SpriteSheet)> {
+        let mut sheets = self.path_sheet_map
+            .iter()
+            .map(|(path, sheet)| (path.as_str(), sheet))
+            .collect::<Vec<_>>();
+        sheets.sort_by_key(|(path, _)| *path);
+        sheets.into_iter()
+    }
+
+    /// Combine the frames of the sheets with the given keys into a single sheet with the given key, in the order of
+    /// the keys. This allows using the animations of a character under one key, even if they were exported to several
+    /// images. The animations of every part are moved to the indices of its frames in the combined sheet.
+    ///
+    /// The combined sheet has no sheet image, so functions like [SpriteSheet::source] return None, and its raw data
+    /// only contains the moved tags and slices. If several parts have an animation with the same name, the first one
+    /// is kept and a warning is logged. Parts which are not created, like unrequested lazy sheets, and parts without
+    /// frame images, like sheets created with [SpriteSheetPlugin::with_regions], are skipped with a warning.
+    pub fn combine(&mut self, key: String, parts: &[&str]) {
+        let mut combined = SpriteSheet::new(
+            Vec::new(),
+            None,
+            UVec2::ZERO,
+            AsepriteData { frames: HashMap::new(), meta: Meta::default(), pages: Vec::new() }
+        );
+
+        for part in parts {
+            let Some(sheet) = self.path_sheet_map.get(*part) else {
+                warn!("The sprite sheet {part} can not be combined into {key}, as it was not created");
+                continue
+            };
+
+            // the combined sheet has no sheet image, so the frames of region sheets could not be shown
+            if sheet.textures.len() != sheet.rects.len() {
+                warn!("The sprite sheet {part} can not be combined into {key}, as it has no frame images");
+                continue
+            }
+
+            let offset = combined.textures.len();
+            combined.textures.extend(sheet.textures.iter().cloned());
+            combined.rects.extend(sheet.rects.iter().copied());
+            combined.frames.extend(sheet.frames.iter().cloned());
+            combined.has_trimmed_frames |= sheet.has_trimmed_frames;
+
+            for name in &sheet.animation_names {
+                if combined.animations.contains_key(name) {
+                    warn!("The animation {name} of the sprite sheet {part} is ignored, as another part of {key} already has an animation with this name");
+                    continue
+                }
+
+                combined.animations.insert(name.clone(), sheet.animations[name].offset(offset));
+                combined.animation_names.push(name.clone());
+            }
+
+            combined.data.meta.frame_tags.extend(sheet.data.meta.frame_tags.iter().map(|tag| FrameTag {
+                from: tag.from + offset,
+                to: tag.to + offset,
+                ..tag.clone()
+            }));
+            combined.data.meta.slices.extend(sheet.data.meta.slices.iter().map(|slice| Slice {
+                keys: slice.keys.iter().map(|slice_key| SliceKey { frame: slice_key.frame + offset, ..slice_key.clone() }).collect(),
+                ..slice.clone()
+            }));
+        }
+
+        self.path_sheet_map.insert(key, combined);
+    }
+
+    /// Return the key of the sheet which was created from the given sheet image, if any.
+    /// Lazy sheets are found as well, even if they were not requested yet.
+    pub fn key_for_source(&self, handle: &Handle<Image>) -> Option<&str> {
+        let id = handle.id();
+
+        self.path_sheet_map
+            .iter()
+            .find(|(_, sheet)| sheet.source_id == Some(id))
+            .map(|(key, _)| key.as_str())
+            .or_else(|| self.unbuilt
+                .iter()
+                .find(|(_, unbuilt)| unbuilt.source.id() == id)
+                .map(|(key, _)| key.as_str())
+            )
+    }
+
+    /// Return the approximate number of bytes the frames of all created sheets occupy, see [SpriteSheet::approx_byte_size].
+    pub fn total_byte_size(&self, images: &Assets<Image>) -> usize {
+        self.path_sheet_map
+            .values()
+            .map(|sheet| sheet.approx_byte_size(images))
+            .sum()
+    }
+
+    /// Describe all created sheets as pretty printed json, for debugging and asset audits. Every sheet is listed with
+    /// its path, frame count, animation names and the size of every frame, sorted by the path.
+    pub fn to_debug_json(&self) -> String {
+        let sheets = self
+            .iter_sorted()
+            .map(|(path, sheet)| serde_json::json!({
+                "path": path,
+                "frame_count": sheet.len(),
+                "animations": sheet.animation_names().collect::<Vec<_>>(),
+                "frame_sizes": sheet.rects.iter().map(|rect| [rect.width, rect.height]).collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>();
+
+        serde_json::to_string_pretty(&sheets).expect("the sheet descriptions should be serializable")
+    }
+
+    /// Return the parsed aseprite data the sheet with the given path was created from.
+    ///
+    /// This is an escape hatch for metadata which is not yet available through [SpriteSheet].
+    pub fn raw_data(&self, path: &str) -> Option<&AsepriteData> {
+        self.path_sheet_map.get(path).map(SpriteSheet::raw_data)
+    }
+}
+
+/// Stores handles to image parts from a bigger sprite sheet image.
+#[derive(Clone)]
+pub struct SpriteSheet {
+    pub textures: Vec<Handle<Image>>,
+    /// The id of the sheet image the frames were split from, if it is known
+    source_id: Option<AssetId<Image>>,
+    /// The sheet image the frames were split from, if it is known and needed
+    source: Option<Handle<Image>>,
+    /// The asset path of the sheet image, if it is known
+    source_path: Option<String>,
+    /// Arbitrary json data attached to the sheet
+    user_meta: Option<serde_json::Value>,
+    /// The size of the sheet image in pixels
+    source_size: UVec2,
+    /// The rectangles of the frames in the sheet image, in the same order as the textures
+    rects: Vec<Rect>,
+    /// Information about each frame, in the same order as the textures
+    frames: Vec<FrameInfo>,
+    /// If any frame was trimmed, computed once from the frames
+    has_trimmed_frames: bool,
+    /// If true, the anchors and pivots are rounded to whole pixels
+    pixel_perfect: bool,
+    /// The animations of this sheet, keyed by their name
+    animations: HashMap<String, AnimationRange>,
+    /// The names of the animations, in the order they were defined
+    animation_names: Vec<String>,
+    /// The aseprite data this sheet was created from
+    data: AsepriteData,
+}
+
+impl SpriteSheet {
+    pub(crate) fn new(
+        handles: impl IntoIterator<Item=Handle<Image>>,
+        source: Option<Handle<Image>>,
+        source_size: UVec2,
+        data: AsepriteData
+    ) -> Self {
+        let frames = data.frame_infos().into_iter().collect::<Vec<_>>();
+        Self {
+            textures: handles.into_iter().collect(),
+            source_id: source.as_ref().map(Handle::id),
+            source,
+            source_path: None,
+            user_meta: None,
+            source_size,
+            rects: data.rect_iter().into_iter().collect(),
+            has_trimmed_frames: frames.iter().any(|frame| frame.trimmed),
+            pixel_perfect: false,
+            frames,
+            animations: animations_from_tags(&data.meta.frame_tags),
+            animation_names: animation_names_from_tags(&data.meta.frame_tags),
+            data
+        }
+    }
+
+    /// Return the rectangles of all frames in the sheet image, in pixels and ordered by the frame index.
+    /// Useful to compute the uv coordinates of the frames in the sheet image.
+    pub fn rects(&self) -> &[Rect] {
+        &self.rects
+    }
+
+    /// Return the rectangle of the frame at the given index in the sheet image.
+    pub fn rect_at(&self, index: usize) -> Option<Rect> {
+        self.rects.get(index).copied()
+    }
+
+    /// Return the rectangle of the frame at the given index in the sheet image as a bevy rect, in pixels.
+    /// Useful for ui or gizmo code, like drawing the frame regions over the sheet image.
+    pub fn frame_bevy_rect(&self, index: usize) -> Option<bevy_math::Rect> {
+        let rect = self.rects.get(index)?;
+        let min = Vec2::new(rect.position.x as f32, rect.position.y as f32);
+        Some(bevy_math::Rect::from_corners(min, min + Vec2::new(rect.width as f32, rect.height as f32)))
+    }
+
+    /// Return the rectangle of the frame at the given index in the sheet image as uv coordinates between 0 and 1,
+    /// for materials which sample a frame from the sheet image (see [SpriteSheet::frame_region]).
+    ///
+    /// Returns None if the index is out of bounds or the size of the sheet image is not known.
+    pub fn frame_uv_rect(&self, index: usize) -> Option<bevy_math::Rect> {
+        let rect = self.frame_bevy_rect(index)?;
+        let size = self.source_size.as_vec2();
+
+        match size.x > 0.0 && size.y > 0.0 {
+            true => Some(bevy_math::Rect::from_corners(rect.min / size, rect.max / size)),
+            false => None
+        }
+    }
+
+    /// Return the sheet image and the rectangle of the frame at the given index inside it. Unlike [SpriteSheet::image_at],
+    /// this needs no frame image, so it also works for sheets created with [SpriteSheetPlugin::with_regions].
+    ///
+    /// Returns None if the index is out of bounds or the sheet image is not known, see [SpriteSheet::source].
+    pub fn frame_region(&self, index: usize) -> Option<(Handle<Image>, Rect)> {
+        Some((self.source.clone()?, *self.rects.get(index)?))
+    }
+
+    /// Return the handle of the sheet image the frames were split from.
+    ///
+    /// The handle keeps the sheet image loaded, so it is only kept if the sheet needs it, which is the case for
+    /// [SpriteSheetPlugin::with_atlases] and [SpriteSheetPlugin::with_regions]. Otherwise the sheet image can be unloaded once
+    /// the frames are created and this returns None. Use [SpriteSheet::source_id] to identify the sheet image instead.
+    pub fn source(&self) -> Option<Handle<Image>> {
+        self.source.clone()
+    }
+
+    /// Return the id of the sheet image the frames were split from, if it is known. Unlike [SpriteSheet::source], this
+    /// is also known if the sheet does not keep the sheet image loaded.
+    pub fn source_id(&self) -> Option<AssetId<Image>> {
+        self.source_id
+    }
+
+    /// Return the asset path of the sheet image the frames were split from, like "animation/my_animation.png".
+    /// Unlike the key of the sheet in [SpriteSheets], it still has its file ending.
+    pub fn source_path(&self) -> Option<&str> {
+        self.source_path.as_deref()
+    }
+
+    /// Return the size of the sheet image the frames were split from, in pixels.
+    pub fn source_size(&self) -> UVec2 {
+        self.source_size
+    }
+
+    /// Return the layers of the sprite the sheet was exported from, ordered from bottom to top. Only available if
+    /// the sheet was exported with layer information.
+    pub fn layers(&self) -> &[LayerInfo] {
+        &self.data.meta.layers
+    }
+
+    /// Return if any frame of the sheet was trimmed, so its sprites need to be offset by the trim information
+    /// (see [FrameInfo::trim_offset]). Sheets without trimmed frames can skip this per frame.
+    pub fn has_trimmed_frames(&self) -> bool {
+        self.has_trimmed_frames
+    }
+
+    /// Return the parsed aseprite data this sheet was created from.
+    pub fn raw_data(&self) -> &AsepriteData {
+        &self.data
+    }
+
+    pub fn image_at(&self, index: usize) -> Handle<Image> {
+        self.textures[index].clone()
+    }
+
+    /// Return the images of the frames with the given indices. Sheets created with [SpriteSheetPlugin::with_regions]
+    /// have no frame images, so no images are returned for them.
+    ///
+    /// Panics if an index is out of bounds.
+    pub fn images_at(&self, indexes: impl IntoIterator<Item=usize>) -> Vec<Handle<Image>> {
+        let indexes = indexes
+            .into_iter()
+            .inspect(|&i| assert!(i < self.len(), "the frame index {i} is out of bounds for a sheet with {} frames", self.len()))
+            .collect::<Vec<_>>();
+        self.all_images_at(indexes).unwrap_or_default()
+    }
+
+    /// Return the images of the frames with the given indices, or None if any index has no frame image.
+    pub(crate) fn all_images_at(&self, indexes: impl IntoIterator<Item=usize>) -> Option<Vec<Handle<Image>>> {
+        indexes.into_iter().map(|i| self.textures.get(i).cloned()).collect()
+    }
+
+    /// Return if the frame at the given index is fully transparent, which means no pixel has an alpha value above zero.
+    ///
+    /// Returns None if the index is out of bounds, the frame image is not loaded or its format has no 8 bit alpha channel.
+    pub fn is_frame_empty(&self, images: &Assets<Image>, index: usize) -> Option<bool> {
+        let image = images.get(self.textures.get(index)?)?;
+        let layout = ChannelLayout::of(image.texture_descriptor.format)?;
+        let data = image.data.as_ref()?;
+
+        Some(crate::core::is_transparent(data, layout.channels, layout.alpha_offset()))
+    }
+
+    /// Return the union of the opaque pixels of all frames of the animation with the given name, which is the area any
+    /// of its frames occupies, for example to size a collider. The rect is in pixels relative to the top left corner of
+    /// the untrimmed frame, with y pointing down, so the trim of the frames is compensated. Frames scaled with
+    /// [SpriteSheetPlugin::with_integer_scale] are measured in the pixels of the sheet image, like the trim.
+    ///
+    /// Returns None if there is no animation with this name, a frame image is not loaded or has no 8 bit alpha channel
+    /// or all frames are fully transparent.
+    pub fn animation_union_bounds(&self, name: &str, images: &Assets<Image>) -> Option<bevy_math::Rect> {
+        let mut union: Option<bevy_math::Rect> = None;
+
+        for index in self.animation(name)?.frame_sequence() {
+            let image = images.get(self.textures.get(index)?)?;
+            let layout = ChannelLayout::of(image.texture_descriptor.format)?;
+            let size = image.texture_descriptor.size;
+            let rect = Rect::new(p!(0, 0), size.width as usize, size.height as usize);
+
+            let Some(bounds) = crate::core::opaque_bounds(image.data.as_ref()?, rect, row_stride(image), layout.channels, layout.alpha_offset()) else {
+                continue
+            };
+
+            // the frame image is larger than its rect in the sheet image if it was scaled
+            let frame_rect = self.rects.get(index)?;
+            let scale = Vec2::new(size.width as f32 / frame_rect.width as f32, size.height as f32 / frame_rect.height as f32);
+
+            let min = self.frames.get(index)?.trim_offset().as_vec2() + Vec2::new(bounds.position.x as f32, bounds.position.y as f32) / scale;
+            let bounds = bevy_math::Rect::from_corners(min, min + Vec2::new(bounds.width as f32, bounds.height as f32) / scale);
+            union = Some(union.map_or(bounds, |union| union.union(bounds)));
+        }
+
+        union
+    }
+
+    /// Return the maximum width and height over the frames with the given indices.
+    ///
+    /// Returns None if no index was given or any index is out of bounds.
+    pub fn bounding_size(&self, indices: impl IntoIterator<Item=usize>) -> Option<UVec2> {
+        indices
+            .into_iter()
+            .map(|i| self.rects.get(i).map(|rect| UVec2::new(rect.width as u32, rect.height as u32)))
+            .reduce(|a, b| Some(a?.max(b?)))
+            .flatten()
+    }
+
+    /// Return how long the frame at the given index is shown.
+    pub fn duration_at(&self, index: usize) -> Option<Duration> {
+        self.frames.get(index).map(|frame| frame.duration)
+    }
+
+    /// Return the information about the frame at the given index, like its duration and trim.
+    pub fn frame_info(&self, index: usize) -> Option<&FrameInfo> {
+        self.frames.get(index)
+    }
+
+    /// Return the information about all frames, ordered by their index.
+    pub fn frame_infos(&self) -> &[FrameInfo] {
+        &self.frames
+    }
+
+    /// Return the user data of the frame at the given index, like an event marker set by the designer.
+    /// The user data of a frame is taken from the frame itself, or else from the first of its cels with user data,
+    /// starting at the bottom layer.
+    pub fn frame_data(&self, index: usize) -> Option<&str> {
+        self.frames.get(index)?.data.as_deref()
+    }
+
+    /// Return the user data of the animation tag with the given name.
+    pub fn animation_data(&self, name: &str) -> Option<&str> {
+        self.data.meta.frame_tags.iter().find(|tag| tag.name == name)?.data.as_deref()
+    }
+
+    /// Replace the image of the frame at the given index with the given image, for example to fill a slot of the sheet
+    /// at runtime. All other data of the frame, like its rect and duration, stays the same.
+    ///
+    /// Returns the replaced handle, or None if the index is out of bounds.
+    pub fn set_frame(&mut self, index: usize, handle: Handle<Image>) -> Option<Handle<Image>> {
+        let texture = self.textures.get_mut(index)?;
+        Some(std::mem::replace(texture, handle))
+    }
+
+    /// Return an owned copy of the image of the frame at the given index. Unlike cloning the handle, the copy
+    /// can be modified and added as a new asset without changing the frame of this sheet.
+    pub fn clone_frame(&self, images: &Assets<Image>, index: usize) -> Option<Image> {
+        images.get(self.textures.get(index)?).cloned()
+    }
+
+    /// Create a copy of this sheet with every frame rotated clockwise by the given number of quarter turns, for example
+    /// to create the sprites of all four directions from a single sheet. The rotated frames are added to the image assets.
+    /// Frames which are not loaded keep their unrotated handle, so every frame stays at its index.
+    ///
+    /// All other data, like the frame rects, stays the same and still describes the unrotated sheet image.
+    pub fn rotated(&self, images: &mut Assets<Image>, quarter_turns: u8) -> SpriteSheet {
+        let textures = self.textures
+            .iter()
+            .map(|handle| match images.get(handle).map(|image| rotate_image_90(image, quarter_turns)) {
+                Some(rotated) => images.add(rotated),
+                None => handle.clone()
+            })
+            .collect();
+
+        SpriteSheet {
+            textures,
+            ..self.clone()
+        }
+    }
+
+    /// Return true if the other sheet has the same number of frames as this sheet and every frame has the same size.
+    /// This allows swapping a sheet with a variant, like a recolored one, without breaking its animations.
+    pub fn is_layout_compatible(&self, other: &SpriteSheet) -> bool {
+        self.layout_diff(other).is_empty()
+    }
+
+    /// Return the indices of all frames whose size differs between this and the other sheet. Frames which only exist
+    /// in one of the sheets are also returned.
+    pub fn layout_diff(&self, other: &SpriteSheet) -> Vec<usize> {
+        (0..self.rects.len().max(other.rects.len()))
+            .filter(|&i| match (self.rects.get(i), other.rects.get(i)) {
+                (Some(a), Some(b)) => a.width != b.width || a.height != b.height,
+                _ => true
+            })
+            .collect()
+    }
+
+    /// Return the image handles of the frames of the given playback range, in the order of the range.
+    /// Sheets without frame images return no handles, see [SpriteSheet::images_at].
+    pub fn handles_for(&self, range: &PlaybackRange) -> Vec<Handle<Image>> {
+        self.images_at(range.frames.iter().copied())
+    }
+
+    /// Return the approximate number of bytes the pixel data of all frames occupy, including their mip levels.
+    /// Frames which are not loaded are not counted.
+    pub fn approx_byte_size(&self, images: &Assets<Image>) -> usize {
+        self.textures
+            .iter()
+            .filter_map(|handle| images.get(handle)?.data.as_ref())
+            .map(Vec::len)
+            .sum()
+    }
+
+    /// Return the number of frames in this sheet.
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    /// Return true if this sheet has no frames.
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// Return the first image of this sheet, or None if the sheet is empty.
+    pub fn first(&self) -> Option<Handle<Image>> {
+        self.textures.first().cloned()
+    }
+
+    /// Return the last image of this sheet, or None if the sheet is empty.
+    pub fn last(&self) -> Option<Handle<Image>> {
+        self.textures.last().cloned()
+    }
+
+    /// Iterate over the handles of all images in this sheet, ordered by their index.
+    pub fn iter(&self) -> std::slice::Iter<'_, Handle<Image>> {
+        self.textures.iter()
+    }
+}
+
+impl IntoIterator for SpriteSheet {
+    type Item = Handle<Image>;
+    type IntoIter = std::vec::IntoIter<Handle<Image>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.textures.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SpriteSheet {
+    type Item = &'a Handle<Image>;
+    type IntoIter = std::slice::Iter<'a, Handle<Image>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
+
+    use bevy_app::{App, TaskPoolPlugin};
+    use bevy_asset::io::memory::{Dir, MemoryAssetReader};
+    use bevy_asset::io::AssetSource;
+    use bevy_asset::{AssetApp, AssetPath, AssetPlugin, AssetServer, Handle, LoadState, RenderAssetUsages};
+    use bevy_color::Color;
+    use bevy_ecs::prelude::*;
+    use bevy_image::Image;
+    use bevy_math::UVec2;
+    use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+    use bevy_state::app::StatesPlugin;
+    use bevy_state::prelude::*;
+    use pad::p;
+
+    use super::{apply_color_key, clamp_tags, format_fits, frame_rects, image_sheet_key, mismatched_size, row_stride, source_sheet_key, split_image, AsepriteData, ExtractOptions, MissingImage, Rect, Size, SpriteSheetPlugin, SpriteSheetSet, SpriteSheetSource, SpriteSheets};
+    use crate::test_utils::{parse, sheet_json, sheet_from_json};
+
+    #[derive(States, Clone, Debug, Default, Eq, PartialEq, Hash)]
+    enum LoadingState {
+        /// Waits for assets before the sheets are created
+        Waiting,
+        #[default]
+        Loading,
+        Done,
+    }
+
+    /// Set by a system which needs the sheets, if they were available when it ran.
+    #[derive(Resource)]
+    struct SheetsAvailable(bool);
+
+    /// Build a headless app with the given plugin and only the plugins it needs, starting in the loading state.
+    fn plugin_app(plugin: SpriteSheetPlugin<LoadingState, LoadingState>) -> App {
+        let mut app = App::new();
+        app
+            .add_plugins((TaskPoolPlugin::default(), AssetPlugin::default(), StatesPlugin))
+            .init_asset::<Image>()
+            .init_state::<LoadingState>()
+            .add_plugins(plugin)
+        ;
+        app
+    }
+
+    /// Keeps the assets of [app_with_failed_image] loaded.
+    #[derive(Resource)]
+    struct KeptHandles(Handle<AsepriteData>, Handle<Image>);
+
+    /// The image resolver of [app_with_failed_image], which points to an image that does not exist.
+    fn missing_hero_image(_json_path: &Path) -> PathBuf {
+        PathBuf::from("hero.png")
+    }
+
+    /// Build an app with the aseprite json file "memory://hero.aseprite.json" in an in memory asset source, whose image
+    /// does not exist. Waits until the json file is loaded and the image failed to load, then enters the loading state,
+    /// so the sheets are created in the next update.
+    fn app_with_failed_image(missing_image: MissingImage) -> App {
+        let dir = Dir::default();
+        dir.insert_asset(Path::new("hero.aseprite.json"), sheet_json(2, "").into_bytes());
+
+        let mut app = App::new();
+        app
+            .register_asset_source("memory", AssetSource::build().with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })))
+            .add_plugins((TaskPoolPlugin::default(), AssetPlugin::default(), StatesPlugin))
+            .init_asset::<Image>()
+            .insert_state(LoadingState::Waiting)
+            .add_plugins(SpriteSheetPlugin::new(LoadingState::Loading, LoadingState::Done)
+                .with_image_path_resolver(missing_hero_image)
+                .with_missing_image(missing_image)
+            )
+        ;
+
+        let asset_server = app.world().resource::<AssetServer>().clone();
+        let json = asset_server.load::<AsepriteData>("memory://hero.aseprite.json");
+        let image = asset_server.load::<Image>("memory://hero.png");
+
+        // the assets are loaded on the io task pool, so the app is updated until both loads finished
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            app.update();
+
+            let json_loaded = matches!(asset_server.get_load_state(json.id()), Some(LoadState::Loaded));
+            let image_failed = matches!(asset_server.get_load_state(image.id()), Some(LoadState::Failed(_)));
+            if json_loaded && image_failed {
+                break
+            }
+
+            assert!(Instant::now() < deadline, "the json file should load and the image should fail to load");
+            std::thread::yield_now();
+        }
+
+        app.insert_resource(KeptHandles(json, image));
+        app.world_mut().resource_mut::<NextState<LoadingState>>().set(LoadingState::Loading);
+        app
+    }
+
+    /// Create a 2x1 image with the given format and a red and a blue pixel in bgra order.
+    fn bgra_image(format: TextureFormat) -> Image {
+        Image::new(
+            Extent3d { width: 2, height: 1, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            vec![0, 0, 255, 255, 255, 0, 0, 255],
+            format,
+            RenderAssetUsages::default()
+        )
+    }
+
+    /// Create a 2x2 rgba image whose rows are padded to 16 bytes. Every pixel has the value of its index in all channels.
+    fn padded_image() -> Image {
+        let mut image = Image::new(
+            Extent3d { width: 2, height: 2, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            vec![0; 16],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default()
+        );
+
+        let mut data = Vec::new();
+        for row in 0..2u8 {
+            data.extend([row * 2; 4]);
+            data.extend([row * 2 + 1; 4]);
+            data.extend([0xff; 8]);
+        }
+        image.data = Some(data);
+        image
+    }
+
+    #[test]
+    fn sheets_are_available_after_the_sprite_sheet_set() {
+        let mut app = plugin_app(SpriteSheetPlugin::new(LoadingState::Loading, LoadingState::Done));
+        app.add_systems(
+            OnEnter(LoadingState::Loading),
+            (|mut commands: Commands, sheets: Option<Res<SpriteSheets>>| commands.insert_resource(SheetsAvailable(sheets.is_some())))
+                .after(SpriteSheetSet)
+        );
+
+        app.update();
+
+        assert!(app.world().get_resource::<SheetsAvailable>().is_some_and(|available| available.0));
+    }
+
+    #[test]
+    fn plugin_can_be_added_twice() {
+        let mut app = plugin_app(SpriteSheetPlugin::new(LoadingState::Loading, LoadingState::Done));
+        app.add_plugins(SpriteSheetPlugin::new(LoadingState::Done, LoadingState::Loading));
+
+        app.update();
+
+        assert!(app.world().contains_resource::<SpriteSheets>());
+    }
+
+    #[test]
+    fn state_is_advanced_after_creating_the_sheets() {
+        let mut app = plugin_app(SpriteSheetPlugin::new(LoadingState::Loading, LoadingState::Done));
+        app.update();
+        app.update();
+
+        assert_eq!(app.world().resource::<State<LoadingState>>().get(), &LoadingState::Done);
+    }
+
+    #[test]
+    fn state_is_unchanged_without_transition() {
+        let mut app = plugin_app(SpriteSheetPlugin::new_without_transition(LoadingState::Loading));
+        app.update();
+        app.update();
+
+        assert!(app.world().contains_resource::<SpriteSheets>());
+        assert_eq!(app.world().resource::<State<LoadingState>>().get(), &LoadingState::Loading);
+    }
+
+    #[test]
+    fn embedded_json_and_image_share_a_prefixed_key() {
+        let json_key = source_sheet_key(&AssetPath::parse("embedded://animation/hero.aseprite.json"), AsepriteData::EXTENSION);
+        let image_key = image_sheet_key(&AssetPath::parse("embedded://animation/hero.png"));
+
+        assert_eq!(json_key, "embedded://animation/hero");
+        assert_eq!(json_key, image_key);
+        assert_ne!(image_key, image_sheet_key(&AssetPath::parse("animation/hero.png")));
+
+        let sheets = SpriteSheets::new([(json_key, sheet_from_json(&sheet_json(2, "")))]);
+        assert_eq!(sheets.get_sheet("embedded://animation/hero").len(), 2);
+        assert!(sheets.raw_data("animation/hero").is_none());
+    }
+
+    #[test]
+    fn padded_rows_are_skipped_when_splitting() {
+        let image = padded_image();
+        assert_eq!(row_stride(&image), 16);
+
+        let frames = split_image(&image, [Rect::new(p!(1, 0), 1, 2)], ExtractOptions::default()).into_iter().collect::<Vec<_>>();
+        assert_eq!(frames[0].data.as_deref(), Some([1, 1, 1, 1, 3, 3, 3, 3].as_slice()));
+    }
+
+    #[test]
+    fn bgra_frames_are_extracted_byte_identical() {
+        let image = bgra_image(TextureFormat::Bgra8UnormSrgb);
+        let frames = split_image(&image, [Rect::new(p!(1, 0), 1, 1)], ExtractOptions::default()).into_iter().collect::<Vec<_>>();
+
+        assert_eq!(frames[0].texture_descriptor.format, TextureFormat::Bgra8UnormSrgb);
+        assert_eq!(frames[0].data.as_deref(), Some([255, 0, 0, 255].as_slice()));
+    }
+
+    #[test]
+    fn color_key_matches_the_channels_of_bgra_images() {
+        let red = Color::srgb(1.0, 0.0, 0.0);
+
+        let mut bgra = bgra_image(TextureFormat::Bgra8UnormSrgb);
+        apply_color_key(&mut bgra, red);
+        assert_eq!(bgra.data.as_deref(), Some([0, 0, 0, 0, 255, 0, 0, 255].as_slice()));
+
+        // the same bytes are blue and red in rgba order, so the other pixel is keyed
+        let mut rgba = bgra_image(TextureFormat::Rgba8UnormSrgb);
+        apply_color_key(&mut rgba, red);
+        assert_eq!(rgba.data.as_deref(), Some([0, 0, 255, 255, 0, 0, 0, 0].as_slice()));
+    }
+
+    #[test]
+    fn sheet_with_a_failed_image_is_skipped() {
+        let mut app = app_with_failed_image(MissingImage::Skip);
+        app.update();
+        app.update();
+
+        assert!(app.world().resource::<SpriteSheets>().raw_data("memory://hero").is_none());
+        assert_eq!(app.world().resource::<State<LoadingState>>().get(), &LoadingState::Done);
+    }
+
+    #[test]
+    #[should_panic(expected = "its image memory://hero.png failed to load")]
+    fn sheet_with_a_failed_image_aborts() {
+        let mut app = app_with_failed_image(MissingImage::Abort);
+        app.update();
+    }
+
+    #[test]
+    fn flipped_frames_are_flipped_back() {
+        let data = parse(r#"{"frames": {
+            "hero 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 2, "h": 1}},
+            "hero 1.aseprite": {"frame": {"x": 0, "y": 0, "w": 2, "h": 1}, "flipX": true}
+        }}"#);
+        let image = bgra_image(TextureFormat::Rgba8UnormSrgb);
+
+        let rects = frame_rects(&data, ExtractOptions::default());
+        assert_eq!(rects.iter().map(|(_, options)| options.flip_x).collect::<Vec<_>>(), [false, true]);
+        assert!(data.frame_infos().into_iter().nth(1).is_some_and(|frame| frame.flip_x));
+
+        let frames = rects
+            .into_iter()
+            .flat_map(|(rect, options)| split_image(&image, [rect], options))
+            .collect::<Vec<_>>();
+        assert_eq!(frames[0].data.as_deref(), Some([0, 0, 255, 255, 255, 0, 0, 255].as_slice()));
+        assert_eq!(frames[1].data.as_deref(), Some([255, 0, 0, 255, 0, 0, 255, 255].as_slice()));
+    }
+
+    #[test]
+    fn flipped_frames_are_flipped_again_when_flipping_the_sheet() {
+        let data = parse(r#"{"frames": {"hero 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 2, "h": 1}, "flipX": true}}}"#);
+
+        let rects = frame_rects(&data, ExtractOptions { flip_x: true, flip_y: true });
+        assert!(!rects[0].1.flip_x);
+        assert!(rects[0].1.flip_y);
+    }
+
+    #[test]
+    fn indexed_sheets_fit_rgba_and_single_channel_images() {
+        let data = parse(r#"{"frames": {}, "meta": {"format": "I8"}}"#);
+        assert_eq!(data.declared_format(), Some("I8"));
+
+        assert!(format_fits(data.declared_format(), TextureFormat::Rgba8UnormSrgb));
+        assert!(format_fits(data.declared_format(), TextureFormat::R8Unorm));
+        assert!(!format_fits(data.declared_format(), TextureFormat::Rgba16Float));
+        assert!(!format_fits(Some("RGBA8888"), TextureFormat::R8Unorm));
+        assert!(format_fits(Some("RGB888"), TextureFormat::R8Unorm));
+    }
+
+    #[test]
+    fn declared_size_is_compared_with_the_image() {
+        let data = parse(r#"{"frames": {}, "meta": {"size": {"w": 64, "h": 32}}}"#);
+
+        assert_eq!(mismatched_size(&data, UVec2::new(64, 32)), None);
+        assert_eq!(mismatched_size(&data, UVec2::new(128, 64)), Some(Size { w: 64, h: 32 }));
+        assert_eq!(mismatched_size(&parse(r#"{"frames": {}}"#), UVec2::new(128, 64)), None);
+    }
+
+    #[test]
+    fn out_of_range_tags_are_clamped() {
+        let data = parse(&sheet_json(3, r#"{"name": "walk", "from": 1, "to": 7}, {"name": "idle", "from": 0, "to": 2}"#));
+        assert_eq!(data.invalid_tags().iter().map(|tag| tag.name.as_str()).collect::<Vec<_>>(), ["walk"]);
+
+        let data = clamp_tags("hero", data);
+        assert!(data.invalid_tags().is_empty());
+        assert_eq!((data.meta.frame_tags[0].from, data.meta.frame_tags[0].to), (1, 2));
+        assert_eq!((data.meta.frame_tags[1].from, data.meta.frame_tags[1].to), (0, 2));
+    }
+
+    #[test]
+    fn tags_of_sheets_without_frames_are_removed() {
+        let data = clamp_tags("hero", parse(&sheet_json(0, r#"{"name": "walk", "from": 0, "to": 1}"#)));
+
+        assert!(data.meta.frame_tags.is_empty());
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use bevy_math::UVec2;
+
+    use crate::{AsepriteData, SpriteSheet};
+
+    /// Return aseprite json data with the given number of 16x16 frames in a single row and the given tags,
+    /// which are written as the elements of the frameTags array.
+    pub(crate) fn sheet_json(frame_count: usize, tags: &str) -> String {
+        let frames = (0..frame_count)
+            .map(|i| format!(r#""sheet {i}.aseprite": {{"frame": {{"x": {}, "y": 0, "w": 16, "h": 16}}, "duration": 100}}"#, i * 16))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(r#"{{"frames": {{{frames}}}, "meta": {{"frameTags": [{tags}]}}}}"#)
+    }
+
+    /// Parse the given aseprite json data, panicking if it is invalid.
+    pub(crate) fn parse(json: &str) -> AsepriteData {
+        AsepriteData::from_json("test.aseprite.json", json.as_bytes()).expect("the test json should be valid")
+    }
+
+    /// Create a sheet without frame images from the given aseprite json data. The source size is the size in
+    /// `meta.size`, or the bounds of the frames if the json does not declare one.
+    pub(crate) fn sheet_from_json(json: &str) -> SpriteSheet {
+        let data = parse(json);
+        let size = match &data.meta.size {
+            Some(size) => UVec2::new(size.w as u32, size.h as u32),
+            None => data.rect_iter()
+                .into_iter()
+                .map(|rect| UVec2::new((rect.position.x as usize + rect.width) as u32, (rect.position.y as usize + rect.height) as u32))
+                .fold(UVec2::ZERO, UVec2::max)
+        };
+        SpriteSheet::new(Vec::new(), None, size, data)
+    }
+
+    /// Create a sheet without frame images with the given number of frames and tags, see [sheet_json].
+    pub(crate) fn sheet_with_tags(frame_count: usize, tags: &str) -> SpriteSheet {
+        sheet_from_json(&sheet_json(frame_count, tags))
+    }
+}
@@ -0,0 +1,39 @@
+use bevy_math::Vec2;
+
+use crate::aseprite_data::Pivot;
+use crate::SpriteSheet;
+
+impl SpriteSheet {
+    /// Return the pivot of the frame at the given index, normalized to the size of the untrimmed frame, so (0, 0) is
+    /// the top left and (1, 1) the bottom right corner.
+    ///
+    /// The pivot of the frame itself is used if it has one. Otherwise the pivot of the first slice which has a pivot at
+    /// this frame is used, or the center of the frame if there is none. Returns None if the index is out of bounds.
+    ///
+    /// With [crate::SpriteSheetPlugin::with_pixel_perfect], the pivot is moved to the top left corner of its pixel.
+    pub fn pivot_at(&self, index: usize) -> Option<Vec2> {
+        let frame = self.frames.get(index)?;
+        let size = Vec2::new(frame.source_size.w as f32, frame.source_size.h as f32);
+
+        let pivot = frame.pivot.or_else(|| self.slice_pivot_at(index));
+
+        let point = match pivot {
+            Some(pivot) if size.x > 0.0 && size.y > 0.0 => Vec2::new(pivot.x as f32, pivot.y as f32),
+            _ => size * 0.5
+        };
+
+        Some(match (self.pixel_perfect, size.x > 0.0 && size.y > 0.0) {
+            (true, true) => point.floor() / size,
+            (_, true) => point / size,
+            (_, false) => Vec2::splat(0.5)
+        })
+    }
+
+    /// Return the pivot of the first slice which has a pivot at the given frame, in pixels relative to the untrimmed frame.
+    fn slice_pivot_at(&self, index: usize) -> Option<Pivot> {
+        self.data.meta.slices
+            .iter()
+            .filter_map(|slice| slice.key_at(index))
+            .find_map(|key| key.pivot.map(|pivot| Pivot { x: key.bounds.x as i32 + pivot.x, y: key.bounds.y as i32 + pivot.y }))
+    }
+}
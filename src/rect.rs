@@ -1,5 +1,6 @@
 use pad::position::Position;
 
+/// A rectangle in an image, with the position of its top left corner and its size in pixels.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct Rect {
     pub position: Position,
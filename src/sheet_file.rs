@@ -0,0 +1,124 @@
+use bevy_app::prelude::*;
+use bevy_asset::io::Reader;
+use bevy_asset::prelude::*;
+use bevy_asset::{AssetLoader, LoadContext, RenderAssetUsages};
+use bevy_image::{CompressedImageFormats, Image, ImageSampler, ImageType, TextureError};
+use bevy_reflect_derive::{Reflect, TypePath};
+use thiserror::Error;
+
+use crate::core::ExtractOptions;
+use crate::error::{RectError, SpriteSheetError};
+use crate::{clamp_tags, finish_sprite_sheet, frame_rects, split_image, validate_rects, AsepriteData, SheetSettings, SpriteSheet};
+
+/// The bytes every ".spritesheet" file starts with.
+const MAGIC: &[u8; 4] = b"SPSH";
+
+/// A sprite sheet loaded from a single ".spritesheet" file, which contains both the aseprite json data and the png
+/// image, so no json file has to be paired with its image. Load it like any other asset, e.g.
+/// `asset_server.load::<SpriteSheetFile>("animation/my_animation.spritesheet")`.
+///
+/// The frames and the sheet image are labeled assets of the file, named "frame<n>" and "image". The sheets are created
+/// with the default settings and are not part of the [SpriteSheets](crate::SpriteSheets) resource.
+///
+/// A file is the magic bytes "SPSH", the length of the json data as little endian u32, the json data and the png
+/// data, see [write_sprite_sheet_file].
+#[derive(Asset, Reflect, Clone)]
+#[reflect(opaque)]
+pub struct SpriteSheetFile(pub SpriteSheet);
+
+/// Create the content of a ".spritesheet" file from the given aseprite json data and png image.
+pub fn write_sprite_sheet_file(json: &[u8], png: &[u8]) -> Vec<u8> {
+    let length = u32::try_from(json.len()).expect("the json data should be smaller than 4 GiB");
+    let mut file = Vec::with_capacity(MAGIC.len() + 4 + json.len() + png.len());
+    file.extend_from_slice(MAGIC);
+    file.extend_from_slice(&length.to_le_bytes());
+    file.extend_from_slice(json);
+    file.extend_from_slice(png);
+    file
+}
+
+/// Errors which can occur while loading a ".spritesheet" file.
+#[derive(Debug, Error)]
+pub enum SpriteSheetFileError {
+    #[error("the sprite sheet file could not be read: {0}")]
+    Io(#[from] std::io::Error),
+    /// The file does not start with the magic bytes or is shorter than its json data.
+    #[error("the file is not a valid sprite sheet file")]
+    InvalidContainer,
+    #[error(transparent)]
+    InvalidJson(#[from] SpriteSheetError),
+    #[error("the image of the sprite sheet file is invalid: {0}")]
+    InvalidImage(#[from] TextureError),
+    /// Frames of the json data which can not be extracted from the image.
+    #[error("the sprite sheet file has {} frames outside of its image", .0.len())]
+    InvalidFrames(Vec<RectError>),
+}
+
+/// Loads ".spritesheet" files as [SpriteSheetFile] assets.
+#[derive(Default, TypePath)]
+pub struct SpriteSheetFileLoader;
+
+impl AssetLoader for SpriteSheetFileLoader {
+    type Asset = SpriteSheetFile;
+    type Settings = ();
+    type Error = SpriteSheetFileError;
+
+    async fn load(&self, reader: &mut dyn Reader, _settings: &(), load_context: &mut LoadContext<'_>) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let (json, png) = split_container(&bytes).ok_or(SpriteSheetFileError::InvalidContainer)?;
+
+        let path = load_context.path().to_string_lossy().to_string();
+        let data = clamp_tags(&path, AsepriteData::from_json(&path, json)?);
+        let image = Image::from_buffer(
+            png,
+            ImageType::Extension("png"),
+            CompressedImageFormats::NONE,
+            true,
+            ImageSampler::Default,
+            RenderAssetUsages::default()
+        )?;
+
+        let rects = frame_rects(&data, ExtractOptions::default());
+        validate_rects(&image, &rects.iter().map(|(rect, _)| *rect).collect::<Vec<_>>()).map_err(SpriteSheetFileError::InvalidFrames)?;
+
+        let handles = rects
+            .into_iter()
+            .flat_map(|(rect, options)| split_image(&image, [rect], options))
+            .enumerate()
+            .map(|(i, frame)| load_context.add_labeled_asset(format!("frame{i}"), frame))
+            .collect::<Vec<_>>();
+
+        let size = image.size();
+        let source = load_context.add_labeled_asset("image".to_string(), image);
+        Ok(SpriteSheetFile(finish_sprite_sheet(handles, Some(source), size, data, &SheetSettings::default())))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["spritesheet"]
+    }
+}
+
+/// Return the json and png data of a ".spritesheet" file, or None if it is malformed.
+fn split_container(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let rest = bytes.strip_prefix(MAGIC)?;
+    let (length, rest) = rest.split_first_chunk::<4>()?;
+    let length = u32::from_le_bytes(*length) as usize;
+
+    match length <= rest.len() {
+        true => Some(rest.split_at(length)),
+        false => None
+    }
+}
+
+/// Registers the [SpriteSheetFile] asset and its loader.
+pub(crate) struct SpriteSheetFilePlugin;
+
+impl Plugin for SpriteSheetFilePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset::<SpriteSheetFile>()
+            .init_asset_loader::<SpriteSheetFileLoader>()
+        ;
+    }
+}
@@ -0,0 +1,68 @@
+use bevy_asset::prelude::*;
+use bevy_reflect_derive::Reflect;
+use serde::Deserialize;
+
+use crate::aseprite_data::{default_duration, Frame};
+use crate::{AsepriteData, FrameTag, FrameValue, Meta, SheetSampler, SpriteSheetSource};
+
+/// A simple, hand written definition of a sprite sheet, as an alternative to aseprite json files.
+/// Loaded from ".sheet.ron" files next to the sheet image if the `ron` feature is enabled.
+///
+/// A definition lists the frame rectangles in order and the named animations, like
+/// `(frames: [(x: 0, y: 0, w: 16, h: 16), (x: 16, y: 0, w: 16, h: 16)], animations: [(name: "walk", from: 0, to: 1)])`.
+/// The sampler of the frames can be set with `sampler: Some(nearest)`.
+#[derive(Asset, Reflect, Deserialize, Clone)]
+#[reflect(opaque)]
+pub struct SheetDefinition {
+    /// The frames of the sheet, ordered by their index.
+    pub frames: Vec<FrameDefinition>,
+    /// The named animations of the sheet, defined like aseprite tags.
+    #[serde(default)]
+    pub animations: Vec<FrameTag>,
+    /// The sampler of the frames, overriding the sampler set on the plugin.
+    #[serde(default)]
+    pub sampler: Option<SheetSampler>,
+}
+
+/// The position, size and duration of a single frame in a [SheetDefinition].
+#[derive(Deserialize, Clone)]
+pub struct FrameDefinition {
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
+    /// How long the frame is shown in milliseconds.
+    #[serde(default = "default_duration")]
+    pub duration: u64,
+}
+
+impl SpriteSheetSource for SheetDefinition {
+    const EXTENSION: &'static str = "sheet.ron";
+
+    fn to_aseprite_data(&self) -> AsepriteData {
+        AsepriteData {
+            // the frame names are the indices, which are sorted the same way as aseprite frame names
+            frames: self.frames
+                .iter()
+                .enumerate()
+                .map(|(index, frame)| (index.to_string(), FrameValue {
+                    frame: Frame { x: frame.x, y: frame.y, w: frame.w, h: frame.h },
+                    duration: frame.duration,
+                    trimmed: false,
+                    sprite_source_size: None,
+                    source_size: None,
+                    pivot: None,
+                    flip_x: false,
+                    flip_y: false,
+                    data: None,
+                }))
+                .collect(),
+            meta: Meta {
+                frame_tags: self.animations.clone(),
+                sampler: self.sampler,
+                ..Meta::default()
+            },
+            pages: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::aseprite_data::{Frame, Pivot};
+use crate::SpriteSheet;
+
+/// The state of a slice at a single frame, like the bounds of a hitbox.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SliceInfo {
+    pub name: String,
+    /// The index of the frame where the key of this state starts
+    pub key_frame: usize,
+    /// The bounds of the slice in pixels, relative to the untrimmed frame
+    pub bounds: Frame,
+    /// The pivot of the slice in pixels, relative to the top left corner of its bounds
+    pub pivot: Option<Pivot>,
+}
+
+impl SpriteSheet {
+    /// Return the state of the slice with the given name at the given frame. Slices can have several keys, so
+    /// their bounds and pivot can change during an animation, like a moving hitbox. The latest key which starts
+    /// at or before the frame is used.
+    ///
+    /// Returns None if there is no slice with this name or its first key starts after the frame.
+    pub fn slice_at_frame(&self, slice_name: &str, frame: usize) -> Option<SliceInfo> {
+        let slice = self.data.meta.slices.iter().find(|slice| slice.name == slice_name)?;
+        let key = slice.key_at(frame)?;
+
+        Some(SliceInfo {
+            name: slice.name.clone(),
+            key_frame: key.frame,
+            bounds: key.bounds,
+            pivot: key.pivot,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::sheet_from_json;
+    use crate::{Frame, Pivot};
+
+    const MOVING_HITBOX: &str = r#"{
+        "frames": {
+            "hero 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 16, "h": 16}},
+            "hero 1.aseprite": {"frame": {"x": 16, "y": 0, "w": 16, "h": 16}},
+            "hero 2.aseprite": {"frame": {"x": 32, "y": 0, "w": 16, "h": 16}},
+            "hero 3.aseprite": {"frame": {"x": 48, "y": 0, "w": 16, "h": 16}}
+        },
+        "meta": {"slices": [{"name": "hitbox", "color": "#0000ffff", "keys": [
+            {"frame": 1, "bounds": {"x": 2, "y": 2, "w": 8, "h": 8}},
+            {"frame": 3, "bounds": {"x": 6, "y": 4, "w": 8, "h": 10}, "pivot": {"x": 4, "y": 10}}
+        ]}]}
+    }"#;
+
+    #[test]
+    fn slice_at_frame_uses_the_latest_key() {
+        let sheet = sheet_from_json(MOVING_HITBOX);
+
+        assert_eq!(sheet.slice_at_frame("hitbox", 0), None);
+        assert_eq!(sheet.slice_at_frame("hitbox", 1).map(|slice| slice.bounds), Some(Frame { x: 2, y: 2, w: 8, h: 8 }));
+        assert_eq!(sheet.slice_at_frame("hitbox", 2).map(|slice| slice.key_frame), Some(1));
+
+        let last = sheet.slice_at_frame("hitbox", 3).expect("the slice should have a key at the last frame");
+        assert_eq!(last.key_frame, 3);
+        assert_eq!(last.bounds, Frame { x: 6, y: 4, w: 8, h: 10 });
+        assert_eq!(last.pivot, Some(Pivot { x: 4, y: 10 }));
+        assert_eq!(sheet.slice_at_frame("hurtbox", 3), None);
+    }
+}
@@ -0,0 +1,129 @@
+use bevy_asset::Asset;
+
+use crate::{AsepriteData, Meta};
+
+/// An asset which describes the frames and animations of a sprite sheet image.
+///
+/// All sources are converted to [AsepriteData], so sheets are created the same way regardless of their source.
+/// The image of a source is found the same way as for aseprite json files.
+pub trait SpriteSheetSource: Asset {
+    /// The file ending of the source assets, without the leading ".". It is removed from the asset path to get the sheet key.
+    const EXTENSION: &'static str;
+
+    /// Convert this source to the aseprite data the sheet is created from.
+    fn to_aseprite_data(&self) -> AsepriteData;
+
+    /// Return the pages of this source. Every page creates a separate sheet, which allows atlases that are split
+    /// into several images. By default, a source has a single page with the image next to it.
+    fn pages(&self) -> Vec<SourcePage> {
+        vec![SourcePage { image: None, data: self.to_aseprite_data() }]
+    }
+}
+
+/// The frames of a sprite sheet source which are stored in one image.
+pub struct SourcePage {
+    /// The file name of the image, relative to the source. If None, the image is found like for aseprite json files.
+    pub image: Option<String>,
+    /// The data the sheet of this page is created from
+    pub data: AsepriteData,
+}
+
+impl SpriteSheetSource for AsepriteData {
+    const EXTENSION: &'static str = "aseprite.json";
+
+    fn to_aseprite_data(&self) -> AsepriteData {
+        self.clone()
+    }
+
+    /// Aseprite data with pages, like a Phaser 3 multi atlas, creates a sheet for every page. Each page has its own
+    /// image size and format, while the rest of the meta data is shared. As the animation tags and slices of the meta
+    /// data can not refer to the frames of a single page, the sheets of the pages have none.
+    fn pages(&self) -> Vec<SourcePage> {
+        if self.pages.is_empty() {
+            return vec![SourcePage { image: None, data: self.clone() }]
+        }
+
+        self.pages
+            .iter()
+            .map(|page| SourcePage {
+                image: Some(page.image.clone()),
+                data: AsepriteData {
+                    frames: page.frames
+                        .iter()
+                        .map(|frame| (frame.filename.clone(), frame.value.clone()))
+                        .collect(),
+                    meta: Meta {
+                        size: page.size,
+                        format: page.format.clone().or_else(|| self.meta.format.clone()),
+                        frame_tags: Vec::new(),
+                        slices: Vec::new(),
+                        ..self.meta.clone()
+                    },
+                    pages: Vec::new(),
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::parse;
+    use crate::{Size, SpriteSheetSource};
+
+    /// A Phaser 3 multi atlas with two pages of different sizes, as exported by TexturePacker.
+    const MULTI_ATLAS: &str = r#"{
+        "textures": [
+            {
+                "image": "hero-0.png",
+                "format": "RGBA8888",
+                "size": {"w": 64, "h": 32},
+                "scale": 1,
+                "frames": [
+                    {"filename": "walk 0.aseprite", "rotated": false, "trimmed": false, "frame": {"x": 0, "y": 0, "w": 32, "h": 32}, "spriteSourceSize": {"x": 0, "y": 0, "w": 32, "h": 32}, "sourceSize": {"w": 32, "h": 32}},
+                    {"filename": "walk 1.aseprite", "rotated": false, "trimmed": false, "frame": {"x": 32, "y": 0, "w": 32, "h": 32}, "spriteSourceSize": {"x": 0, "y": 0, "w": 32, "h": 32}, "sourceSize": {"w": 32, "h": 32}}
+                ]
+            },
+            {
+                "image": "hero-1.png",
+                "format": "RGBA8888",
+                "size": {"w": 32, "h": 32},
+                "scale": 1,
+                "frames": [
+                    {"filename": "walk 2.aseprite", "rotated": false, "trimmed": false, "frame": {"x": 0, "y": 0, "w": 32, "h": 32}, "spriteSourceSize": {"x": 0, "y": 0, "w": 32, "h": 32}, "sourceSize": {"w": 32, "h": 32}}
+                ]
+            }
+        ],
+        "meta": {"app": "https://www.codeandweb.com/texturepacker", "version": "3.0", "smartupdate": "$TexturePacker:SmartUpdate$"}
+    }"#;
+
+    #[test]
+    fn multi_atlas_creates_a_page_for_every_texture() {
+        let pages = parse(MULTI_ATLAS).pages();
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].image.as_deref(), Some("hero-0.png"));
+        assert_eq!(pages[1].image.as_deref(), Some("hero-1.png"));
+        assert_eq!(pages[0].data.frames.len(), 2);
+        assert_eq!(pages[1].data.frames.len(), 1);
+        assert!(pages[1].data.frames.contains_key("walk 2.aseprite"));
+    }
+
+    #[test]
+    fn multi_atlas_pages_have_their_own_size() {
+        let pages = parse(MULTI_ATLAS).pages();
+
+        assert_eq!(pages[0].data.meta.size, Some(Size { w: 64, h: 32 }));
+        assert_eq!(pages[1].data.meta.size, Some(Size { w: 32, h: 32 }));
+        assert_eq!(pages[1].data.declared_format(), Some("RGBA8888"));
+    }
+
+    #[test]
+    fn data_without_textures_is_a_single_page() {
+        let pages = parse(r#"{"frames": {}, "meta": {"size": {"w": 16, "h": 16}}}"#).pages();
+
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].image, None);
+        assert_eq!(pages[0].data.meta.size, Some(Size { w: 16, h: 16 }));
+    }
+}
@@ -0,0 +1,38 @@
+use bevy_asset::prelude::*;
+use bevy_image::Image;
+use bevy_math::Vec2;
+use bevy_sprite::Anchor;
+
+use crate::SpriteSheet;
+
+impl SpriteSheet {
+    /// Return the image of the frame at the given index and the anchor to show it with, so the base anchor keeps its
+    /// position in the untrimmed frame. Without this, the frames of a trimmed animation shift around, as each trimmed
+    /// frame has a different size and offset.
+    ///
+    /// The base anchor is relative to the untrimmed frame. Untrimmed frames are returned with the base anchor.
+    /// With [crate::SpriteSheetPlugin::with_pixel_perfect], the anchor point is moved to the top left corner of its
+    /// pixel, so the trimmed frame is offset by whole pixels.
+    /// Returns None if the index is out of bounds.
+    pub fn sprite_for_frame(&self, index: usize, base_anchor: Anchor) -> Option<(Handle<Image>, Anchor)> {
+        let handle = self.textures.get(index)?.clone();
+        let frame = self.frames.get(index)?;
+
+        if !frame.trimmed || frame.sprite_source_size.w == 0 || frame.sprite_source_size.h == 0 {
+            return Some((handle, base_anchor))
+        }
+
+        // the anchor point in pixels of the untrimmed frame, from its top left corner. Anchors point upwards.
+        let source_size = Vec2::new(frame.source_size.w as f32, frame.source_size.h as f32);
+        let mut point = Vec2::new(base_anchor.0.x + 0.5, 0.5 - base_anchor.0.y) * source_size;
+        if self.pixel_perfect {
+            point = point.floor();
+        }
+
+        let offset = frame.trim_offset().as_vec2();
+        let trimmed_size = Vec2::new(frame.sprite_source_size.w as f32, frame.sprite_source_size.h as f32);
+        let relative = (point - offset) / trimmed_size;
+
+        Some((handle, Anchor(Vec2::new(relative.x - 0.5, 0.5 - relative.y))))
+    }
+}
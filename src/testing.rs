@@ -0,0 +1,81 @@
+//! Helpers to test systems which use sprite sheets, without a window or renderer.
+
+use bevy_app::prelude::*;
+use bevy_app::TaskPoolPlugin;
+use bevy_asset::prelude::*;
+use bevy_image::Image;
+use bevy_state::app::StatesPlugin;
+use bevy_state::prelude::*;
+use bevy_state::state::FreelyMutableState;
+
+use crate::{build_sprite_sheets_from, AsepriteData, SpriteSheetPlugin};
+
+/// Build a headless app with only the plugins required by the given sprite sheet plugin: the task pools, the
+/// asset plugin, the states plugin and the image assets. Both states of the plugin are initialized with their default.
+///
+/// Use [insert_test_sheets] to add sheets from images and aseprite data created in memory.
+pub fn test_app_with_sprite_sheets<CreateState, NextState>(plugin: SpriteSheetPlugin<CreateState, NextState>) -> App
+where
+    CreateState: States + FreelyMutableState + Default,
+    NextState: States + FreelyMutableState + Default,
+{
+    let mut app = App::new();
+    app
+        .add_plugins((TaskPoolPlugin::default(), AssetPlugin::default(), StatesPlugin))
+        .init_asset::<Image>()
+        .init_state::<CreateState>()
+        .init_state::<NextState>()
+        .add_plugins(plugin)
+    ;
+    app
+}
+
+/// Create sprite sheets from the given keys, images and aseprite data and insert them as the
+/// [SpriteSheets](crate::SpriteSheets) resource of the app, like [build_sprite_sheets_from]. This replaces the sheets
+/// created by the plugin.
+pub fn insert_test_sheets(app: &mut App, sheets: impl IntoIterator<Item=(String, Image, AsepriteData)>) {
+    let sprite_sheets = build_sprite_sheets_from(sheets, &mut app.world_mut().resource_mut::<Assets<Image>>());
+    app.insert_resource(sprite_sheets);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::{Assets, RenderAssetUsages};
+    use bevy_image::Image;
+    use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+    use bevy_state::prelude::*;
+
+    use super::{insert_test_sheets, test_app_with_sprite_sheets};
+    use crate::test_utils::{parse, sheet_json};
+    use crate::{SpriteSheetPlugin, SpriteSheets};
+
+    #[derive(States, Clone, Debug, Default, Eq, PartialEq, Hash)]
+    enum LoadingState {
+        #[default]
+        Loading,
+        Done,
+    }
+
+    #[test]
+    fn test_sheets_can_be_used_by_systems() {
+        let mut app = test_app_with_sprite_sheets(SpriteSheetPlugin::new(LoadingState::Loading, LoadingState::Done));
+        app.update();
+
+        let image = Image::new_fill(
+            Extent3d { width: 48, height: 16, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            &[255, 0, 0, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default()
+        );
+        let data = parse(&sheet_json(3, r#"{"name": "walk", "from": 0, "to": 2, "direction": "forward"}"#));
+        insert_test_sheets(&mut app, [("hero".to_string(), image, data)]);
+
+        let sheets = app.world().resource::<SpriteSheets>();
+        let images = app.world().resource::<Assets<Image>>();
+        let frames = sheets.get_sheet("hero").animation_frames("walk").expect("the animation should exist");
+
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|frame| images.get(frame).is_some_and(|image| image.size().x == 16)));
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use bevy_asset::prelude::*;
+use bevy_reflect_derive::Reflect;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{source_sheet_key, MatchedSheet, SpriteSheet};
+
+/// Arbitrary json data attached to a sprite sheet, like hitboxes or anchor offsets.
+/// Loaded from files next to the aseprite json file if enabled with
+/// [SpriteSheetPlugin::with_user_meta](crate::SpriteSheetPlugin::with_user_meta).
+#[derive(Asset, Reflect, Deserialize, Clone)]
+#[reflect(opaque)]
+#[serde(transparent)]
+pub struct SheetUserMeta(pub Value);
+
+/// Attach the loaded user meta data to the matched sheets with the same key.
+pub(crate) fn attach_user_meta(
+    matched_sheets: &mut [MatchedSheet],
+    asset_server: &AssetServer,
+    user_metas: &Assets<SheetUserMeta>,
+    extension: &str,
+) {
+    let mut key_meta_map = user_metas
+        .iter()
+        .filter_map(|(id, meta)| Some((source_sheet_key(&asset_server.get_path(id)?, extension), meta.0.clone())))
+        .collect::<HashMap<_, _>>();
+
+    for matched in matched_sheets {
+        matched.user_meta = key_meta_map.remove(&matched.key);
+    }
+}
+
+impl SpriteSheet {
+    /// Return the user meta data of this sheet, see [SpriteSheetPlugin::with_user_meta](crate::SpriteSheetPlugin::with_user_meta).
+    pub fn user_meta(&self) -> Option<&Value> {
+        self.user_meta.as_ref()
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::{AsepriteData, SpriteSheetSource};
+
+/// The problems found by [validate_folder]. Errors prevent a sheet from being created, while warnings describe
+/// assets which are probably not intended, like images without an aseprite json file.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub errors: Vec<ValidationIssue>,
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Return true if no errors were found. Warnings are allowed.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn error(&mut self, path: &Path, message: impl Into<String>) {
+        self.errors.push(ValidationIssue { path: path.to_path_buf(), message: message.into() });
+    }
+
+    fn warning(&mut self, path: &Path, message: impl Into<String>) {
+        self.warnings.push(ValidationIssue { path: path.to_path_buf(), message: message.into() });
+    }
+}
+
+/// A single problem with the file at the given path.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ValidationIssue {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// Check all aseprite json files and images in the given folder and its subfolders without a running app, for
+/// example to validate the assets in CI. The images are the files with one of the given file endings, like "png".
+///
+/// Every json file must parse and have its image next to it, with the same name and one of the file endings (or the
+/// images of its pages). The frames must lie within the image, whose size is read from the png header. Images of
+/// other formats are only checked for existence. Unreadable files, missing images and invalid frames are errors,
+/// while images without a json file, tags with missing frames and a differing sheet size are warnings.
+pub fn validate_folder(dir: &Path, extensions: &[&str]) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let mut json_paths = Vec::new();
+    let mut image_paths = Vec::new();
+
+    if let Err(error) = collect_files(dir, extensions, &mut json_paths, &mut image_paths) {
+        report.error(dir, format!("the folder can not be read: {error}"));
+        return report
+    }
+
+    let images_by_stem = image_paths
+        .iter()
+        .map(|path| (file_stem(path), path.clone()))
+        .collect::<HashMap<_, _>>();
+    let mut paired_images = HashSet::new();
+
+    for json_path in &json_paths {
+        let data = match fs::read(json_path) {
+            Ok(bytes) => AsepriteData::from_json(&json_path.to_string_lossy(), &bytes),
+            Err(error) => {
+                report.error(json_path, format!("the file can not be read: {error}"));
+                continue
+            }
+        };
+
+        let data = match data {
+            Ok(data) => data,
+            Err(error) => {
+                report.error(json_path, error.to_string());
+                continue
+            }
+        };
+
+        for page in data.pages() {
+            let image_path = match &page.image {
+                Some(image) => Some(json_path.with_file_name(image)).filter(|path| path.is_file()),
+                None => images_by_stem.get(&file_stem(json_path)).cloned()
+            };
+
+            let Some(image_path) = image_path else {
+                report.error(json_path, "no image was found for the aseprite json file");
+                continue
+            };
+
+            validate_page(&mut report, json_path, &image_path, &page.data);
+            paired_images.insert(image_path);
+        }
+    }
+
+    for image_path in image_paths.iter().filter(|path| !paired_images.contains(*path)) {
+        report.warning(image_path, "the image has no aseprite json file");
+    }
+
+    report
+}
+
+/// Check the frames and tags of a single page against its image.
+fn validate_page(report: &mut ValidationReport, json_path: &Path, image_path: &Path, data: &AsepriteData) {
+    for tag in data.invalid_tags() {
+        report.warning(json_path, format!("the tag {} selects the frames {} to {}, but the sheet only has {} frames", tag.name, tag.from, tag.to, data.frames.len()));
+    }
+
+    let size = match png_size(image_path) {
+        Ok(Some(size)) => size,
+        Ok(None) => return,
+        Err(error) => {
+            report.error(image_path, format!("the image can not be read: {error}"));
+            return
+        }
+    };
+
+    if let Some(meta_size) = data.meta.size
+        && (meta_size.w, meta_size.h) != size {
+        report.warning(json_path, format!("the sheet size {}x{} differs from the image size {}x{}", meta_size.w, meta_size.h, size.0, size.1));
+    }
+
+    let rects = data.rect_iter().into_iter().collect::<Vec<_>>();
+    if let Err(errors) = crate::core::validate_rects(size.0, size.1, &rects) {
+        for error in errors {
+            report.error(json_path, error.to_string());
+        }
+    }
+}
+
+/// Recursively collect the aseprite json files and the images with one of the given file endings.
+fn collect_files(dir: &Path, extensions: &[&str], json_paths: &mut Vec<PathBuf>, image_paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_files(&path, extensions, json_paths, image_paths)?;
+            continue
+        }
+
+        let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        if name.ends_with(&format!(".{}", AsepriteData::EXTENSION)) {
+            json_paths.push(path);
+        } else if extensions.iter().any(|extension| name.ends_with(&format!(".{extension}"))) {
+            image_paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Return the path without everything after the first "." of the file name, like the sheet keys.
+fn file_stem(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(name.split('.').next().unwrap_or_default())
+}
+
+/// Read the width and height from the header of a png file. Returns None if the file is not a png.
+fn png_size(path: &Path) -> io::Result<Option<(usize, usize)>> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+    // the signature is followed by the IHDR chunk: its length, its type and the width and height as big endian
+    let mut header = [0; 24];
+    match fs::File::open(path)?.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error)
+    }
+
+    if header[..8] != SIGNATURE || &header[12..16] != b"IHDR" {
+        return Ok(None)
+    }
+
+    let width = u32::from_be_bytes(header[16..20].try_into().expect("the slice has 4 bytes"));
+    let height = u32::from_be_bytes(header[20..24].try_into().expect("the slice has 4 bytes"));
+    Ok(Some((width as usize, height as usize)))
+}